@@ -0,0 +1,207 @@
+use serde_json::Value as JsonValue;
+
+use crate::bindings::supabase::wrappers::{
+    http, time,
+    types::{Context, FdwError, FdwResult, OptionsType, Row},
+    utils,
+};
+use crate::client::{header, Client};
+use crate::debug::ScanPlan;
+use crate::incremental::Watermark;
+use crate::log;
+use crate::mapping::ColumnMap;
+use crate::pagination::{scan_limit, Limits, Pager, Paging};
+use crate::scan::Scan;
+use crate::template::{fill_template, Escape, Placeholders};
+use crate::Provider;
+
+// GitHub provider, reads any list endpoint of the REST API, e.g. `events` or
+// `repos/{owner}/{repo}/issues`, see https://docs.github.com/en/rest
+#[derive(Debug, Default)]
+pub(crate) struct GithubFdw {
+    base_url: String,
+    client: Client,
+    headers: Vec<(String, String)>,
+    // sleep until the rate limit resets instead of failing the scan
+    wait_on_rate_limit: bool,
+    scan: Scan,
+}
+
+impl GithubFdw {
+    pub(crate) fn new(ctx: &Context) -> Result<Self, FdwError> {
+        let opts = ctx.get_options(OptionsType::Server);
+
+        let mut headers = vec![
+            ("user-agent".to_owned(), "Wasm FDW".to_owned()),
+            (
+                "accept".to_owned(),
+                "application/vnd.github+json".to_owned(),
+            ),
+            ("x-github-api-version".to_owned(), "2022-11-28".to_owned()),
+        ];
+        // the token is optional, anonymous calls get a lower rate limit
+        let token = match opts.get("api_key_id") {
+            Some(secret_id) => Some(
+                utils::get_vault_secret(&secret_id)
+                    .ok_or(format!("cannot find vault secret {}", secret_id))?,
+            ),
+            None => opts.get("api_key"),
+        };
+        if let Some(token) = token {
+            headers.push(("authorization".to_owned(), format!("Bearer {}", token)));
+        }
+
+        let wait_on_rate_limit = match opts.require_or("rate_limit", "error").as_str() {
+            "error" => false,
+            "wait" => true,
+            other => return Err(format!("invalid rate_limit option: {}", other)),
+        };
+
+        Ok(Self {
+            base_url: opts.require_or("api_url", "https://api.github.com"),
+            client: Client::new(ctx)?,
+            headers,
+            wait_on_rate_limit,
+            ..Default::default()
+        })
+    }
+
+    // send a request, waiting for or failing on an exhausted rate limit
+    fn fetch(&self, req: &http::Request) -> Result<http::Response, FdwError> {
+        loop {
+            let resp = self.client.send(req)?;
+            let limited = matches!(resp.status_code, 403 | 429)
+                && header(&resp, "x-ratelimit-remaining") == Some("0");
+            if !limited {
+                http::error_for_status(&resp)?;
+                return Ok(resp);
+            }
+            self.wait_for_reset(reset_time(&resp))?;
+        }
+    }
+
+    // sleep until the rate limit resets, or fail when waiting is not enabled
+    fn wait_for_reset(&self, reset: i64) -> FdwResult {
+        if !self.wait_on_rate_limit {
+            return Err(format!(
+                "GitHub API rate limit exceeded, it resets at {}. Set api_key_id for a higher limit, or the rate_limit 'wait' server option to wait for the reset",
+                time::epoch_ms_to_rfc3339(reset * 1_000_000)?
+            ));
+        }
+        let wait_secs = (reset - time::epoch_secs()).max(0) + 1;
+        log::notice(&format!(
+            "GitHub API rate limit exceeded, waiting {} seconds for the reset",
+            wait_secs
+        ));
+        time::sleep(wait_secs as u64 * 1000);
+        Ok(())
+    }
+}
+
+// epoch seconds when the rate limit of a response resets, now if unknown
+fn reset_time(resp: &http::Response) -> i64 {
+    header(resp, "x-ratelimit-reset")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(time::epoch_secs)
+}
+
+impl Provider for GithubFdw {
+    fn begin_scan(&mut self, ctx: &Context) -> FdwResult {
+        self.client.begin_scan(ctx)?;
+        let opts = ctx.get_options(OptionsType::Table);
+        let server_opts = ctx.get_options(OptionsType::Server);
+
+        // the object is a path template filled from quals or table options,
+        // e.g. `repos/{owner}/{repo}/issues` with `owner` and `repo` columns
+        // incremental scans pass the watermark as `{watermark}`, e.g. to
+        // `repos/{owner}/{repo}/issues?state=all&since={watermark}`
+        let object = opts.require("object")?;
+        self.scan.watermark = Watermark::new(ctx, &format!("github:{}", object))?;
+        let mut placeholders = Placeholders::new(ctx, vec![&opts]);
+        if let Some(watermark) = &self.scan.watermark {
+            placeholders = placeholders.with_value("watermark", watermark.since().to_owned());
+        }
+        let path = fill_template(&object, &mut placeholders, Escape::Url)?;
+        // the largest page, unless the object asks for another size
+        let url = match path.split_once('?') {
+            Some((_, query)) if query.split('&').any(|p| p.starts_with("per_page=")) => {
+                format!("{}/{}", self.base_url, path)
+            }
+            Some(_) => format!("{}/{}&per_page=100", self.base_url, path),
+            None => format!("{}/{}?per_page=100", self.base_url, path),
+        };
+        self.scan.columns = ColumnMap::parse(&opts.require_or("columns", ""))?;
+        for (col, value) in placeholders.used_quals() {
+            self.scan.columns.insert_fallback(col, value);
+        }
+
+        let first = http::Request {
+            method: http::Method::Get,
+            url,
+            headers: self.headers.clone(),
+            body: String::default(),
+        };
+        let limit = scan_limit(ctx).filter(|_| self.scan.watermark.is_none());
+        let limits = Limits::from_options(&opts, &server_opts)?;
+        let mut pager = Pager::new(Paging::LinkHeader, limit, limits);
+
+        // reset time of a limit the last page exhausted
+        let mut exhausted: Option<i64> = None;
+        let rows = pager.fetch_all(
+            &first,
+            |req| {
+                // pause before the next page rather than have it rejected
+                if let Some(reset) = exhausted.take() {
+                    self.wait_for_reset(reset)?;
+                }
+                let resp = self.fetch(req)?;
+                if header(&resp, "x-ratelimit-remaining") == Some("0") {
+                    exhausted = Some(reset_time(&resp));
+                }
+                Ok(resp)
+            },
+            |resp| {
+                let resp_json: JsonValue =
+                    serde_json::from_str(&resp.body).map_err(|e| e.to_string())?;
+
+                // list endpoints return an array, search endpoints wrap it in `items`
+                let page = match resp_json {
+                    JsonValue::Array(rows) => rows,
+                    JsonValue::Object(mut obj) => match obj.remove("items") {
+                        Some(JsonValue::Array(rows)) => rows,
+                        _ => vec![JsonValue::Object(obj)],
+                    },
+                    _ => return Err("response should be a JSON array or object".to_owned()),
+                };
+                Ok((JsonValue::Null, page))
+            },
+        )?;
+
+        self.scan.plan = ScanPlan {
+            pushed: placeholders
+                .used_quals()
+                .iter()
+                .map(|(col, _)| col.clone())
+                .collect(),
+            limit_used: limit.is_some(),
+            pages: pager.pages(),
+        };
+        self.scan.set_rows(ctx, rows, pager.truncated())
+    }
+
+    fn iter_scan(&mut self, ctx: &Context, row: &Row) -> Result<Option<u32>, FdwError> {
+        self.scan.iter_scan(ctx, row, None)
+    }
+
+    fn re_scan(&mut self, ctx: &Context) -> FdwResult {
+        if !self.scan.rewind(ctx) {
+            return self.begin_scan(ctx);
+        }
+        Ok(())
+    }
+
+    fn end_scan(&mut self, _ctx: &Context) -> FdwResult {
+        self.scan.end_scan();
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use serde_json::Value as JsonValue;
+use std::cmp::Ordering;
+
+use crate::bindings::supabase::wrappers::{
+    time,
+    types::{Cell, Context, FdwError, OptionsType, Value},
+};
+use crate::mapping::ColumnMap;
+use crate::template::cell_text;
+use crate::{fdw_name, metadata_value, set_metadata_value};
+
+// watermark of an incremental scan, the largest value of the incremental
+// column seen so far. Watermarks are kept in the wrapper's stats metadata by
+// table key, so they outlive the connection.
+//
+//   incremental         'true' to only fetch rows newer than the watermark
+//   incremental_column  column the watermark is taken from, `updated_at`
+//   watermark_key       key of the watermark, defaults to one per object
+//   incremental_start   watermark of the first scan, `1970-01-01T00:00:00Z`
+//   watermark_reset     watermark to go back to, applied once per value
+//
+// A qual such as `updated_at >= '2024-01-01'` on the incremental column
+// replaces the watermark for one scan, which then stores nothing. A scan
+// with a new `watermark_reset` value starts from it and stores what it saw
+// even if that is older, and the applied value is kept under `{key}:reset`
+// so later scans move on from there.
+#[derive(Debug)]
+pub(crate) struct Watermark {
+    fdw_name: String,
+    key: String,
+    column: String,
+    // watermark the scan requests rows after
+    since: String,
+    // a qual replaced the stored watermark, the scan doesn't store one
+    overridden: bool,
+    // `watermark_reset` value this scan applies
+    reset: Option<String>,
+    // largest value seen by the scan
+    seen: Option<JsonValue>,
+}
+
+impl Watermark {
+    // the watermark of an incremental scan, None if the table is not
+    // incremental
+    pub(crate) fn new(ctx: &Context, default_key: &str) -> Result<Option<Self>, FdwError> {
+        let opts = ctx.get_options(OptionsType::Table);
+        if opts.require_or("incremental", "false") != "true" {
+            return Ok(None);
+        }
+
+        let fdw_name = fdw_name(ctx);
+        let key = opts.require_or("watermark_key", default_key);
+        let column = opts.require_or("incremental_column", "updated_at");
+
+        let qual = ctx
+            .get_quals()
+            .into_iter()
+            .find(|qual| qual.field() == column && matches!(qual.operator().as_str(), ">" | ">="));
+        let overridden = qual.is_some();
+        let reset = opts.get("watermark_reset").filter(|reset| {
+            metadata_value(&fdw_name, &reset_key(&key)) != Some(reset.as_str().into())
+        });
+        let stored = match qual {
+            Some(qual) => match qual.value() {
+                Value::Cell(Cell::Timestamp(ts) | Cell::Timestamptz(ts)) => {
+                    Some(time::epoch_ms_to_rfc3339(ts)?)
+                }
+                Value::Cell(cell) => cell_text(&cell),
+                Value::Array(_) => None,
+            },
+            None if reset.is_some() => reset.clone(),
+            None => metadata_value(&fdw_name, &key)
+                .as_ref()
+                .and_then(|v| v.as_str())
+                .map(str::to_owned),
+        };
+        let since =
+            stored.unwrap_or_else(|| opts.require_or("incremental_start", "1970-01-01T00:00:00Z"));
+
+        Ok(Some(Self {
+            fdw_name,
+            key,
+            column,
+            since,
+            overridden,
+            reset,
+            seen: None,
+        }))
+    }
+
+    // the watermark to request rows after
+    pub(crate) fn since(&self) -> &str {
+        &self.since
+    }
+
+    // take the incremental column of a scanned row into account
+    pub(crate) fn observe(
+        &mut self,
+        src_row: &JsonValue,
+        columns: &ColumnMap,
+    ) -> Result<(), FdwError> {
+        let Some(value) = columns.lookup(src_row, &self.column)? else {
+            return Ok(());
+        };
+        if value.is_null() {
+            return Ok(());
+        }
+        let newer = match &self.seen {
+            Some(seen) => compare(value, seen) == Ordering::Greater,
+            None => true,
+        };
+        if newer {
+            self.seen = Some(value.clone());
+        }
+        Ok(())
+    }
+
+    // store the largest value seen as the new watermark, call it only once
+    // all rows of the scan were read. Scans with a qual on the column store
+    // nothing, and only a reset moves the watermark backwards.
+    pub(crate) fn save(&self) {
+        if self.overridden {
+            return;
+        }
+        let since = JsonValue::String(self.since.clone());
+        let seen = match (&self.seen, &self.reset) {
+            (Some(seen), _) => seen,
+            // a reset scan that saw no rows still goes back
+            (None, Some(_)) => &since,
+            (None, None) => return,
+        };
+        // only a reset moves the watermark backwards
+        if let (Some(old), None) = (metadata_value(&self.fdw_name, &self.key), &self.reset) {
+            if compare(&old, seen) != Ordering::Less {
+                return;
+            }
+        }
+        let value = match seen {
+            JsonValue::String(_) => seen.clone(),
+            other => JsonValue::String(other.to_string()),
+        };
+        set_metadata_value(&self.fdw_name, &self.key, Some(value));
+        if let Some(reset) = &self.reset {
+            set_metadata_value(
+                &self.fdw_name,
+                &reset_key(&self.key),
+                Some(JsonValue::String(reset.clone())),
+            );
+        }
+    }
+}
+
+// key of the last applied `watermark_reset` of a watermark
+fn reset_key(key: &str) -> String {
+    format!("{}:reset", key)
+}
+
+// order watermark values as timestamps, numbers or strings
+fn compare(a: &JsonValue, b: &JsonValue) -> Ordering {
+    let number = |v: &JsonValue| {
+        v.as_f64()
+            .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+    };
+    let timestamp = |v: &JsonValue| v.as_str().and_then(|s| time::parse_from_rfc3339(s).ok());
+    let text = |v: &JsonValue| match v {
+        JsonValue::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    if let (Some(a), Some(b)) = (timestamp(a), timestamp(b)) {
+        return a.cmp(&b);
+    }
+    if let (Some(a), Some(b)) = (number(a), number(b)) {
+        return a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+    }
+    text(a).cmp(&text(b))
+}
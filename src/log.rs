@@ -0,0 +1,76 @@
+use crate::bindings::supabase::wrappers::{
+    types::{Context, FdwResult, OptionsType},
+    utils,
+};
+
+// verbosity of the wrapper's messages, each level includes the ones before.
+// Errors are returned to Postgres and fail the query at any level, so the
+// `error` option value reports no messages, like `off`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Level {
+    Off,
+    Warning,
+    Notice,
+    Info,
+    // requests and pushdown, see `debug`
+    Debug,
+}
+
+// level of the current scan or modify, all messages go through it
+static mut LEVEL: Level = Level::Notice;
+
+// take the level from the `log_level` option, table options first, then
+// server options. `debug 'true'` is short for `log_level 'debug'`.
+pub(crate) fn configure(ctx: &Context) -> FdwResult {
+    let table_opts = ctx.get_options(OptionsType::Table);
+    let server_opts = ctx.get_options(OptionsType::Server);
+    let get = |name: &str| table_opts.get(name).or_else(|| server_opts.get(name));
+
+    let level = match get("log_level").as_deref().unwrap_or("notice") {
+        "off" | "error" => Level::Off,
+        "warning" => Level::Warning,
+        "notice" => Level::Notice,
+        "info" => Level::Info,
+        "debug" => Level::Debug,
+        other => return Err(format!("invalid log_level option: {}", other)),
+    };
+    let level = match get("debug").as_deref() {
+        Some("true") => Level::Debug,
+        _ => level,
+    };
+    unsafe {
+        LEVEL = level;
+    }
+    Ok(())
+}
+
+// whether messages of a level are reported, to skip building them if not
+pub(crate) fn enabled(level: Level) -> bool {
+    unsafe { level <= LEVEL }
+}
+
+pub(crate) fn warning(msg: &str) {
+    if enabled(Level::Warning) {
+        utils::report_warning(msg);
+    }
+}
+
+pub(crate) fn notice(msg: &str) {
+    if enabled(Level::Notice) {
+        utils::report_notice(msg);
+    }
+}
+
+pub(crate) fn info(msg: &str) {
+    if enabled(Level::Info) {
+        utils::report_info(msg);
+    }
+}
+
+// Postgres has no client level below INFO that psql shows, so debug
+// messages are INFO messages with a prefix
+pub(crate) fn debug(msg: &str) {
+    if enabled(Level::Debug) {
+        utils::report_info(&format!("debug: {}", msg));
+    }
+}
@@ -0,0 +1,279 @@
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+use crate::bindings::supabase::wrappers::{
+    time,
+    types::{Cell, FdwError, TypeOid},
+};
+
+// convert a JSON value to a cell of the target column type, numbers and
+// booleans in strings are accepted as APIs often quote them
+pub(crate) fn json_to_cell(src: &JsonValue, type_oid: TypeOid) -> Result<Option<Cell>, FdwError> {
+    if src.is_null() {
+        return Ok(None);
+    }
+
+    let as_i64 = || {
+        src.as_i64()
+            .or_else(|| src.as_f64().map(|v| v as i64))
+            .or_else(|| src.as_str().and_then(|v| v.trim().parse().ok()))
+    };
+    let as_f64 = || {
+        src.as_f64()
+            .or_else(|| src.as_str().and_then(|v| v.trim().parse().ok()))
+    };
+
+    let cell = match type_oid {
+        TypeOid::Bool => src
+            .as_bool()
+            .or_else(|| src.as_str().and_then(|v| v.parse().ok()))
+            .map(Cell::Bool),
+        TypeOid::I8 => as_i64().map(|v| Cell::I8(v as _)),
+        TypeOid::I16 => as_i64().map(|v| Cell::I16(v as _)),
+        TypeOid::I32 => as_i64().map(|v| Cell::I32(v as _)),
+        TypeOid::I64 => as_i64().map(Cell::I64),
+        TypeOid::F32 => as_f64().map(|v| Cell::F32(v as _)),
+        TypeOid::F64 => as_f64().map(Cell::F64),
+        TypeOid::Numeric => as_f64().map(Cell::Numeric),
+        TypeOid::String => Some(Cell::String(match src.as_str() {
+            Some(s) => s.to_owned(),
+            None => src.to_string(),
+        })),
+        TypeOid::Date => match src.as_str() {
+            Some(s) => {
+                // take the date part of a date or timestamp string
+                let date = s.get(..10).unwrap_or(s);
+                let ts = time::parse_from_str(date, "%Y-%m-%d")?;
+                Some(Cell::Date(ts / 1_000_000))
+            }
+            None => None,
+        },
+        TypeOid::Timestamp => match src.as_str() {
+            Some(s) => Some(Cell::Timestamp(time::parse_from_rfc3339(s)?)),
+            None => None,
+        },
+        TypeOid::Timestamptz => match src.as_str() {
+            Some(s) => Some(Cell::Timestamptz(time::parse_from_rfc3339(s)?)),
+            None => None,
+        },
+        TypeOid::Json => Some(Cell::Json(src.to_string())),
+    };
+
+    Ok(cell)
+}
+
+// where a column is read from in a source row
+#[derive(Debug, Clone)]
+struct ColumnPath {
+    pointer: String,
+    required: bool,
+}
+
+// column to source field mapping from the `columns` table option, e.g.
+// `city=address.locality, amount=/billing/amount_money/amount, id!`. A path is
+// a JSON pointer or a dotted path, and a trailing `!` marks the column as
+// required. Unmapped columns read the top-level field of the same name.
+#[derive(Debug, Default)]
+pub(crate) struct ColumnMap {
+    paths: HashMap<String, ColumnPath>,
+    // values of columns the source rows lack, used when a field is missing
+    fallbacks: HashMap<String, JsonValue>,
+}
+
+impl ColumnMap {
+    pub(crate) fn parse(columns: &str) -> Result<Self, FdwError> {
+        let mut map = Self::default();
+        for item in columns.split(',').map(str::trim) {
+            if item.is_empty() {
+                continue;
+            }
+            let (item, required) = match item.strip_suffix('!') {
+                Some(item) => (item.trim_end(), true),
+                None => (item, false),
+            };
+            let (col, path) = match item.split_once('=') {
+                Some((col, path)) => (col.trim(), path.trim()),
+                None => (item, item),
+            };
+            if col.is_empty() || path.is_empty() {
+                return Err(format!("invalid column mapping: {}", item));
+            }
+            map.paths.insert(
+                col.to_owned(),
+                ColumnPath {
+                    pointer: to_pointer(path),
+                    required,
+                },
+            );
+        }
+        Ok(map)
+    }
+
+    // map a column to a path unless the `columns` option already did
+    pub(crate) fn insert_default(&mut self, col: &str, path: &str) {
+        self.paths.entry(col.to_owned()).or_insert(ColumnPath {
+            pointer: to_pointer(path),
+            required: false,
+        });
+    }
+
+    // use `value` for a column whose field is missing from the source row
+    pub(crate) fn insert_fallback(&mut self, col: &str, value: &str) {
+        self.fallbacks
+            .insert(col.to_owned(), JsonValue::String(value.to_owned()));
+    }
+
+    // the source value of a column, missing fields are None unless the
+    // column is required
+    pub(crate) fn lookup<'a>(
+        &'a self,
+        src_row: &'a JsonValue,
+        col: &str,
+    ) -> Result<Option<&'a JsonValue>, FdwError> {
+        let value = match self.paths.get(col) {
+            Some(path) => match src_row.pointer(&path.pointer) {
+                None if path.required && !self.fallbacks.contains_key(col) => {
+                    return Err(format!(
+                        "required column '{}' not found in source row at '{}'",
+                        col, path.pointer
+                    ))
+                }
+                value => value,
+            },
+            None => src_row.get(col),
+        };
+        Ok(value.or_else(|| self.fallbacks.get(col)))
+    }
+}
+
+// turn a dotted path such as `address.locality` into a JSON pointer, paths
+// starting with `/` are pointers already
+pub(crate) fn to_pointer(path: &str) -> String {
+    if path.starts_with('/') {
+        return path.to_owned();
+    }
+    path.split('.')
+        .map(|seg| format!("/{}", seg.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn to_pointer_converts_dotted_paths() {
+        assert_eq!(to_pointer("address.locality"), "/address/locality");
+        assert_eq!(to_pointer("items.0.id"), "/items/0/id");
+        assert_eq!(to_pointer("id"), "/id");
+        assert_eq!(to_pointer("a~b.c/d"), "/a~0b/c~1d");
+    }
+
+    #[test]
+    fn to_pointer_keeps_pointers() {
+        assert_eq!(to_pointer("/billing/amount"), "/billing/amount");
+        assert_eq!(to_pointer("/a.b"), "/a.b");
+    }
+
+    #[test]
+    fn parse_maps_paths_and_required_columns() {
+        let map = ColumnMap::parse(
+            " city = address.locality, amount=/billing/amount, id! , name=info.name !",
+        )
+        .unwrap();
+        assert_eq!(map.paths["city"].pointer, "/address/locality");
+        assert!(!map.paths["city"].required);
+        assert_eq!(map.paths["amount"].pointer, "/billing/amount");
+        assert_eq!(map.paths["id"].pointer, "/id");
+        assert!(map.paths["id"].required);
+        assert_eq!(map.paths["name"].pointer, "/info/name");
+        assert!(map.paths["name"].required);
+    }
+
+    #[test]
+    fn parse_skips_empty_items() {
+        let map = ColumnMap::parse("").unwrap();
+        assert!(map.paths.is_empty());
+        let map = ColumnMap::parse("id,,").unwrap();
+        assert_eq!(map.paths.len(), 1);
+    }
+
+    #[test]
+    fn parse_rejects_empty_columns_and_paths() {
+        assert!(ColumnMap::parse("=address.city").is_err());
+        assert!(ColumnMap::parse("city=").is_err());
+        assert!(ColumnMap::parse("!").is_err());
+    }
+
+    #[test]
+    fn lookup_reads_mapped_and_top_level_fields() {
+        let map = ColumnMap::parse("city=address.locality, first=tags.0").unwrap();
+        let row = json!({
+            "id": "A1",
+            "address": { "locality": "Paris" },
+            "tags": ["x", "y"]
+        });
+        assert_eq!(map.lookup(&row, "city").unwrap(), Some(&json!("Paris")));
+        assert_eq!(map.lookup(&row, "first").unwrap(), Some(&json!("x")));
+        assert_eq!(map.lookup(&row, "id").unwrap(), Some(&json!("A1")));
+        assert_eq!(map.lookup(&row, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn lookup_fails_on_a_missing_required_field() {
+        let map = ColumnMap::parse("city=address.locality!").unwrap();
+        let err = map.lookup(&json!({"address": {}}), "city").unwrap_err();
+        assert!(err.contains("city"), "{}", err);
+        assert!(err.contains("/address/locality"), "{}", err);
+    }
+
+    #[test]
+    fn lookup_uses_fallbacks_for_missing_fields() {
+        let mut map = ColumnMap::parse("owner=repo.owner!").unwrap();
+        map.insert_fallback("owner", "octocat");
+        map.insert_fallback("repo", "hello");
+        let row = json!({"repo": "world"});
+        assert_eq!(map.lookup(&row, "owner").unwrap(), Some(&json!("octocat")));
+        // the row's own field wins over the fallback
+        assert_eq!(map.lookup(&row, "repo").unwrap(), Some(&json!("world")));
+    }
+
+    #[test]
+    fn insert_default_keeps_the_mapped_path() {
+        let mut map = ColumnMap::parse("ver=meta.version").unwrap();
+        map.insert_default("ver", "version");
+        map.insert_default("version", "version");
+        assert_eq!(map.paths["ver"].pointer, "/meta/version");
+        assert_eq!(map.paths["version"].pointer, "/version");
+    }
+
+    #[test]
+    fn json_to_cell_accepts_quoted_numbers_and_booleans() {
+        let cell = |src: JsonValue, type_oid| json_to_cell(&src, type_oid).unwrap();
+        assert!(matches!(
+            cell(json!("12"), TypeOid::I64),
+            Some(Cell::I64(12))
+        ));
+        assert!(matches!(
+            cell(json!(12.7), TypeOid::I32),
+            Some(Cell::I32(12))
+        ));
+        assert!(matches!(
+            cell(json!("true"), TypeOid::Bool),
+            Some(Cell::Bool(true))
+        ));
+        assert!(matches!(cell(json!("1.5"), TypeOid::F64), Some(Cell::F64(v)) if v == 1.5));
+        assert!(cell(json!("abc"), TypeOid::I64).is_none());
+        assert!(cell(JsonValue::Null, TypeOid::String).is_none());
+    }
+
+    #[test]
+    fn json_to_cell_writes_other_values_as_text() {
+        let cell = |src: JsonValue, type_oid| json_to_cell(&src, type_oid).unwrap();
+        assert!(matches!(cell(json!(42), TypeOid::String), Some(Cell::String(s)) if s == "42"));
+        assert!(
+            matches!(cell(json!({"a": 1}), TypeOid::Json), Some(Cell::Json(s)) if s == r#"{"a":1}"#)
+        );
+    }
+}
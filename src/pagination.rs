@@ -0,0 +1,813 @@
+use serde_json::Value as JsonValue;
+use std::borrow::Cow;
+
+use crate::bindings::supabase::wrappers::{
+    http,
+    types::{Context, FdwError, FdwResult, Options},
+};
+use crate::log;
+use crate::mapping::to_pointer;
+use crate::url_encode;
+
+// where a paging parameter is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ParamIn {
+    Query,
+    Body,
+}
+
+// how a list endpoint returns the rest of its results
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Paging {
+    // everything comes in one response
+    None,
+    // a cursor at `pointer` in the response is sent back as `param`
+    Cursor {
+        pointer: Cow<'static, str>,
+        param: Cow<'static, str>,
+        param_in: ParamIn,
+    },
+    // the next page URL is in the `Link: <url>; rel="next"` header
+    LinkHeader,
+    // pages are counted from 1, `size` rows per page
+    PageNumber {
+        param: Cow<'static, str>,
+        size_param: Cow<'static, str>,
+        size: u64,
+    },
+    // rows are skipped by an offset, `size` rows per page
+    Offset {
+        param: Cow<'static, str>,
+        limit_param: Cow<'static, str>,
+        size: u64,
+    },
+    // the next page URL is at `pointer` in the response
+    NextUrl {
+        pointer: Cow<'static, str>,
+    },
+}
+
+impl Paging {
+    // paging from the `paging` option and its parameters:
+    //
+    //   cursor    cursor_path (cursor), cursor_param (cursor), cursor_in (query|body)
+    //   link      -
+    //   page      page_param (page), page_size_param (per_page), page_size (100)
+    //   offset    offset_param (offset), limit_param (limit), page_size (100)
+    //   next_url  next_url_path (next)
+    pub(crate) fn from_options(opts: &Options) -> Result<Self, FdwError> {
+        let opt = |name: &str, default: &str| Cow::Owned(opts.require_or(name, default));
+        let page_size = || {
+            opts.require_or("page_size", "100")
+                .parse::<u64>()
+                .ok()
+                .filter(|size| *size > 0)
+                .ok_or("page_size option must be a positive integer".to_owned())
+        };
+
+        let paging = match opts.require_or("paging", "none").as_str() {
+            "none" => Self::None,
+            "cursor" => Self::Cursor {
+                pointer: Cow::Owned(to_pointer(&opts.require_or("cursor_path", "cursor"))),
+                param: opt("cursor_param", "cursor"),
+                param_in: match opts.require_or("cursor_in", "query").as_str() {
+                    "query" => ParamIn::Query,
+                    "body" => ParamIn::Body,
+                    other => return Err(format!("invalid cursor_in option: {}", other)),
+                },
+            },
+            "link" => Self::LinkHeader,
+            "page" => Self::PageNumber {
+                param: opt("page_param", "page"),
+                size_param: opt("page_size_param", "per_page"),
+                size: page_size()?,
+            },
+            "offset" => Self::Offset {
+                param: opt("offset_param", "offset"),
+                limit_param: opt("limit_param", "limit"),
+                size: page_size()?,
+            },
+            "next_url" => Self::NextUrl {
+                pointer: Cow::Owned(to_pointer(&opts.require_or("next_url_path", "next"))),
+            },
+            other => return Err(format!("unknown paging: {}", other)),
+        };
+        Ok(paging)
+    }
+}
+
+// what a scan does when it reaches one of its limits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum OnLimit {
+    #[default]
+    Error,
+    // warn and return the rows fetched so far
+    Warning,
+}
+
+// pages a scan may fetch when the `max_pages` option is not set, so an API
+// that never stops paging can't loop forever
+const DEFAULT_MAX_PAGES: u64 = 1000;
+
+// caps on what a scan fetches, so a mistaken table can't pull an unbounded
+// result into the instance's memory. From table options, then server
+// options:
+//
+//   max_pages           pages a scan may fetch, 1000 by default
+//   max_rows            rows a scan may fetch
+//   max_response_bytes  size of one response body
+//   on_limit            'error' to fail the scan, or 'warning' to stop it
+#[derive(Debug, Clone)]
+pub(crate) struct Limits {
+    max_pages: u64,
+    max_rows: Option<u64>,
+    max_response_bytes: Option<u64>,
+    on_limit: OnLimit,
+}
+
+impl Limits {
+    pub(crate) fn from_options(
+        table_opts: &Options,
+        server_opts: &Options,
+    ) -> Result<Self, FdwError> {
+        let get = |name: &str| table_opts.get(name).or_else(|| server_opts.get(name));
+        let number = |name: &str| {
+            get(name)
+                .map(|v| {
+                    v.parse::<u64>()
+                        .map_err(|_| format!("invalid {} option: {}", name, v))
+                })
+                .transpose()
+        };
+        let on_limit = match get("on_limit").as_deref().unwrap_or("error") {
+            "error" => OnLimit::Error,
+            "warning" => OnLimit::Warning,
+            other => return Err(format!("invalid on_limit option: {}", other)),
+        };
+        Ok(Self {
+            max_pages: number("max_pages")?.unwrap_or(DEFAULT_MAX_PAGES),
+            max_rows: number("max_rows")?,
+            max_response_bytes: number("max_response_bytes")?,
+            on_limit,
+        })
+    }
+
+    pub(crate) fn max_response_bytes(&self) -> Option<u64> {
+        self.max_response_bytes
+    }
+}
+
+// the page to request next
+#[derive(Debug, PartialEq, Eq)]
+enum NextPage {
+    First,
+    Cursor(String),
+    Url(String),
+    Number(u64),
+}
+
+// walks the pages of one scan, it changes the first page request into the
+// request of the current page and decides from each response whether there
+// is another page
+#[derive(Debug)]
+pub(crate) struct Pager {
+    paging: Paging,
+    next: Option<NextPage>,
+    pages: u64,
+    rows: u64,
+    // rows Postgres needs, if it told us
+    limit: Option<u64>,
+    limits: Limits,
+    // the scan stopped at a limit with rows left out
+    truncated: bool,
+}
+
+impl Pager {
+    pub(crate) fn new(paging: Paging, limit: Option<u64>, limits: Limits) -> Self {
+        Self {
+            paging,
+            next: Some(NextPage::First),
+            pages: 0,
+            rows: 0,
+            limit,
+            limits,
+            truncated: false,
+        }
+    }
+
+    // fetch the pages of a scan. `fetch` sends a request, `parse` takes the
+    // rows out of a response and returns them with the rest of the response
+    // JSON, which cursors are read from.
+    pub(crate) fn fetch_all(
+        &mut self,
+        first: &http::Request,
+        mut fetch: impl FnMut(&http::Request) -> http::HttpResult,
+        mut parse: impl FnMut(&http::Response) -> Result<(JsonValue, Vec<JsonValue>), FdwError>,
+    ) -> Result<Vec<JsonValue>, FdwError> {
+        let mut rows = Vec::new();
+        while let Some(req) = self.next_request(first)? {
+            let resp = fetch(&req)?;
+            if !self.accept(&resp)? {
+                break;
+            }
+            let (resp_json, mut page) = parse(&resp)?;
+            self.advance(&resp, &resp_json, &mut page)?;
+            rows.extend(page);
+        }
+        Ok(rows)
+    }
+
+    // fetch a list of requests, e.g. one GET per id, each response is a page
+    // of the scan and counts towards its limits
+    pub(crate) fn fetch_each(
+        &mut self,
+        reqs: &[http::Request],
+        mut fetch: impl FnMut(&http::Request) -> http::HttpResult,
+        mut parse: impl FnMut(&http::Response) -> Result<Vec<JsonValue>, FdwError>,
+    ) -> Result<Vec<JsonValue>, FdwError> {
+        let mut rows = Vec::new();
+        for (i, req) in reqs.iter().enumerate() {
+            if self.next.is_none() {
+                break;
+            }
+            let resp = fetch(req)?;
+            if !self.accept(&resp)? {
+                break;
+            }
+            let mut page = parse(&resp)?;
+            self.count_page(&page);
+            let next = (i + 1 < reqs.len()).then_some(NextPage::Number(self.pages));
+            self.set_next(next, &mut page)?;
+            rows.extend(page);
+        }
+        Ok(rows)
+    }
+
+    // pages fetched so far
+    pub(crate) fn pages(&self) -> u64 {
+        self.pages
+    }
+
+    // whether the scan stopped at a limit before fetching all rows
+    pub(crate) fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    // check the size of a response before it is parsed, false if the scan
+    // stops without it
+    fn accept(&mut self, resp: &http::Response) -> Result<bool, FdwError> {
+        let Some(max_bytes) = self.limits.max_response_bytes else {
+            return Ok(true);
+        };
+        if resp.body.len() as u64 <= max_bytes {
+            return Ok(true);
+        }
+        self.reach_limit(format!(
+            "scan stopped at a response of {} bytes, over the max_response_bytes option of {}. Check the table options, add quals to narrow the scan down, or raise the option",
+            resp.body.len(),
+            max_bytes
+        ))?;
+        Ok(false)
+    }
+
+    // fail the scan at a limit, or warn and stop it there
+    fn reach_limit(&mut self, msg: String) -> FdwResult {
+        match self.limits.on_limit {
+            OnLimit::Error => Err(msg),
+            OnLimit::Warning => {
+                log::warning(&format!("{}, returning the rows fetched so far", msg));
+                self.next = None;
+                self.truncated = true;
+                Ok(())
+            }
+        }
+    }
+
+    // the request of the next page, or None when all pages are fetched
+    fn next_request(&self, first: &http::Request) -> Result<Option<http::Request>, FdwError> {
+        let Some(next) = &self.next else {
+            return Ok(None);
+        };
+
+        let mut req = first.clone();
+        match (&self.paging, next) {
+            (_, NextPage::Url(url)) => req.url = url.clone(),
+            (
+                Paging::Cursor {
+                    param, param_in, ..
+                },
+                NextPage::Cursor(cursor),
+            ) => match param_in {
+                ParamIn::Query => req.url = add_query(&req.url, param, cursor),
+                ParamIn::Body => {
+                    let mut body: JsonValue = if req.body.is_empty() {
+                        JsonValue::Object(Default::default())
+                    } else {
+                        serde_json::from_str(&req.body).map_err(|e| e.to_string())?
+                    };
+                    body[param.as_ref()] = JsonValue::String(cursor.clone());
+                    req.body = body.to_string();
+                }
+            },
+            (
+                Paging::PageNumber {
+                    param,
+                    size_param,
+                    size,
+                },
+                next,
+            ) => {
+                let page = match next {
+                    NextPage::Number(page) => *page,
+                    _ => 1,
+                };
+                req.url = add_query(&req.url, size_param, &size.to_string());
+                req.url = add_query(&req.url, param, &page.to_string());
+            }
+            (
+                Paging::Offset {
+                    param,
+                    limit_param,
+                    size,
+                },
+                next,
+            ) => {
+                let offset = match next {
+                    NextPage::Number(offset) => *offset,
+                    _ => 0,
+                };
+                req.url = add_query(&req.url, limit_param, &size.to_string());
+                req.url = add_query(&req.url, param, &offset.to_string());
+            }
+            _ => {}
+        }
+        Ok(Some(req))
+    }
+
+    // record a fetched page and work out the next one, the page loses the
+    // rows over `max_rows` when the scan stops there
+    fn advance(
+        &mut self,
+        resp: &http::Response,
+        resp_json: &JsonValue,
+        page: &mut Vec<JsonValue>,
+    ) -> FdwResult {
+        let page_rows = self.count_page(page);
+        let next = match &self.paging {
+            Paging::None => None,
+            Paging::Cursor { pointer, .. } => resp_json
+                .pointer(pointer)
+                .and_then(|v| v.as_str())
+                .filter(|v| !v.is_empty())
+                .map(|v| NextPage::Cursor(v.to_owned())),
+            Paging::LinkHeader => link_next(resp).map(NextPage::Url),
+            Paging::NextUrl { pointer } => resp_json
+                .pointer(pointer)
+                .and_then(|v| v.as_str())
+                .filter(|v| !v.is_empty())
+                .map(|v| NextPage::Url(v.to_owned())),
+            // a short page is the last one
+            Paging::PageNumber { size, .. } => {
+                (page_rows >= *size).then_some(NextPage::Number(self.pages + 1))
+            }
+            Paging::Offset { size, .. } => {
+                (page_rows >= *size).then_some(NextPage::Number(self.rows))
+            }
+        };
+        self.set_next(next, page)
+    }
+
+    // count a fetched page and its rows
+    fn count_page(&mut self, page: &[JsonValue]) -> u64 {
+        let page_rows = page.len() as u64;
+        self.pages += 1;
+        self.rows += page_rows;
+        page_rows
+    }
+
+    // take the next page of a fetched one, or stop the scan at a limit
+    fn set_next(&mut self, next: Option<NextPage>, page: &mut Vec<JsonValue>) -> FdwResult {
+        // an API handing back the cursor or URL just used would repeat the
+        // same page forever
+        if next.is_some() && next == self.next {
+            log::warning("scan stopped, the API returned the same next page again");
+            self.next = None;
+            self.truncated = true;
+            return Ok(());
+        }
+
+        // stop once Postgres has all the rows it asked for
+        let satisfied = self.limit.is_some_and(|limit| self.rows >= limit);
+        self.next = next.filter(|_| !satisfied);
+
+        if let Some(max_rows) = self.limits.max_rows {
+            if self.rows > max_rows || (self.rows == max_rows && self.next.is_some()) {
+                self.reach_limit(format!(
+                    "scan stopped after {} rows, add more quals to narrow it down or raise the max_rows option",
+                    max_rows
+                ))?;
+                let over = (self.rows - max_rows) as usize;
+                page.truncate(page.len() - over);
+                self.rows = max_rows;
+            }
+        }
+        if self.next.is_some() && self.pages >= self.limits.max_pages {
+            self.reach_limit(format!(
+                "scan stopped after {} pages, add more quals to narrow it down or raise the max_pages option",
+                self.pages
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+// rows a scan needs to fetch, known only when Postgres pushes down a LIMIT
+// and there are no quals, as rows filtered out locally would fall short
+pub(crate) fn scan_limit(ctx: &Context) -> Option<u64> {
+    if !ctx.get_quals().is_empty() {
+        return None;
+    }
+    ctx.get_limit()
+        .map(|limit| (limit.count() + limit.offset()).max(0) as u64)
+}
+
+// the URL of `rel="next"` in a `Link` header, e.g.
+// `<https://api.github.com/events?page=2>; rel="next", <...>; rel="last"`
+fn link_next(resp: &http::Response) -> Option<String> {
+    resp.headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("link"))
+        .flat_map(|(_, value)| value.split(','))
+        .find_map(|link| {
+            let (url, params) = link.split_once(';')?;
+            params
+                .split(';')
+                .any(|p| {
+                    let p = p.trim().replace(' ', "");
+                    p == "rel=\"next\"" || p == "rel=next"
+                })
+                .then(|| {
+                    url.trim()
+                        .trim_start_matches('<')
+                        .trim_end_matches('>')
+                        .to_owned()
+                })
+        })
+}
+
+// set a query parameter of a URL, replacing it if it is already there
+fn add_query(url: &str, name: &str, value: &str) -> String {
+    let (base, query) = url.split_once('?').unwrap_or((url, ""));
+    let mut params: Vec<&str> = query
+        .split('&')
+        .filter(|p| !p.is_empty() && p.split('=').next() != Some(name))
+        .collect();
+    let param = format!("{}={}", name, url_encode(value));
+    params.push(&param);
+    format!("{}?{}", base, params.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn no_limits() -> Limits {
+        Limits {
+            max_pages: DEFAULT_MAX_PAGES,
+            max_rows: None,
+            max_response_bytes: None,
+            on_limit: OnLimit::Error,
+        }
+    }
+
+    fn request(url: &str) -> http::Request {
+        http::Request {
+            method: http::Method::Get,
+            url: url.to_owned(),
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    fn response(headers: &[(&str, &str)], body: JsonValue) -> http::Response {
+        http::Response {
+            url: String::new(),
+            status_code: 200,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: body.to_string(),
+        }
+    }
+
+    // fetch pages from canned responses, keyed by request URL, and return
+    // the URLs requested
+    fn fetch_pages(
+        pager: &mut Pager,
+        first: &http::Request,
+        pages: &[(&str, http::Response)],
+    ) -> Result<(Vec<JsonValue>, Vec<String>), FdwError> {
+        let mut urls = Vec::new();
+        let rows = pager.fetch_all(
+            first,
+            |req| {
+                urls.push(req.url.clone());
+                pages
+                    .iter()
+                    .find(|(url, _)| *url == req.url)
+                    .map(|(_, resp)| resp.clone())
+                    .ok_or(format!("unexpected request {}", req.url))
+            },
+            |resp| {
+                let resp_json: JsonValue = serde_json::from_str(&resp.body).unwrap();
+                let rows = resp_json["rows"].as_array().cloned().unwrap_or_default();
+                Ok((resp_json, rows))
+            },
+        )?;
+        Ok((rows, urls))
+    }
+
+    #[test]
+    fn link_next_finds_the_next_link() {
+        let resp = response(
+            &[(
+                "Link",
+                "<https://api.github.com/events?page=1>; rel=\"prev\", <https://api.github.com/events?page=3>; rel=\"next\", <https://api.github.com/events?page=9>; rel=\"last\"",
+            )],
+            json!([]),
+        );
+        assert_eq!(
+            link_next(&resp).as_deref(),
+            Some("https://api.github.com/events?page=3")
+        );
+    }
+
+    #[test]
+    fn link_next_accepts_unquoted_rel_and_extra_params() {
+        let resp = response(
+            &[(
+                "link",
+                "<https://x.test/a?page=2>; title=\"more\"; rel=next",
+            )],
+            json!([]),
+        );
+        assert_eq!(link_next(&resp).as_deref(), Some("https://x.test/a?page=2"));
+    }
+
+    #[test]
+    fn link_next_is_none_on_the_last_page() {
+        let resp = response(
+            &[(
+                "link",
+                "<https://x.test/a?page=1>; rel=\"first\", <https://x.test/a?page=1>; rel=\"prev\"",
+            )],
+            json!([]),
+        );
+        assert_eq!(link_next(&resp), None);
+        assert_eq!(link_next(&response(&[], json!([]))), None);
+    }
+
+    #[test]
+    fn add_query_appends_or_replaces() {
+        assert_eq!(
+            add_query("https://x.test/a", "page", "2"),
+            "https://x.test/a?page=2"
+        );
+        assert_eq!(
+            add_query("https://x.test/a?q=1&page=1", "page", "2"),
+            "https://x.test/a?q=1&page=2"
+        );
+        assert_eq!(
+            add_query("https://x.test/a?per_page_x=1", "per_page", "10"),
+            "https://x.test/a?per_page_x=1&per_page=10"
+        );
+        assert_eq!(
+            add_query("https://x.test/a?", "cursor", "a b/c"),
+            "https://x.test/a?cursor=a%20b%2Fc"
+        );
+    }
+
+    #[test]
+    fn cursor_paging_follows_the_cursor() {
+        let paging = Paging::Cursor {
+            pointer: Cow::Borrowed("/cursor"),
+            param: Cow::Borrowed("cursor"),
+            param_in: ParamIn::Query,
+        };
+        let mut pager = Pager::new(paging, None, no_limits());
+        let pages = [
+            (
+                "https://x.test/a",
+                response(&[], json!({"rows": [1, 2], "cursor": "c1"})),
+            ),
+            (
+                "https://x.test/a?cursor=c1",
+                response(&[], json!({"rows": [3], "cursor": ""})),
+            ),
+        ];
+        let (rows, urls) = fetch_pages(&mut pager, &request("https://x.test/a"), &pages).unwrap();
+        assert_eq!(rows, vec![json!(1), json!(2), json!(3)]);
+        assert_eq!(urls.len(), 2);
+        assert_eq!(pager.pages(), 2);
+        assert!(!pager.truncated());
+    }
+
+    #[test]
+    fn cursor_paging_in_the_body() {
+        let paging = Paging::Cursor {
+            pointer: Cow::Borrowed("/cursor"),
+            param: Cow::Borrowed("cursor"),
+            param_in: ParamIn::Body,
+        };
+        let mut pager = Pager::new(paging, None, no_limits());
+        let mut first = request("https://x.test/search");
+        first.body = json!({"limit": 100}).to_string();
+        pager.next = Some(NextPage::Cursor("c1".to_owned()));
+        let req = pager.next_request(&first).unwrap().unwrap();
+        assert_eq!(req.url, "https://x.test/search");
+        let body: JsonValue = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body, json!({"limit": 100, "cursor": "c1"}));
+    }
+
+    #[test]
+    fn page_number_paging_stops_at_a_short_page() {
+        let paging = Paging::PageNumber {
+            param: Cow::Borrowed("page"),
+            size_param: Cow::Borrowed("per_page"),
+            size: 2,
+        };
+        let mut pager = Pager::new(paging, None, no_limits());
+        let pages = [
+            (
+                "https://x.test/a?per_page=2&page=1",
+                response(&[], json!({"rows": [1, 2]})),
+            ),
+            (
+                "https://x.test/a?per_page=2&page=2",
+                response(&[], json!({"rows": [3]})),
+            ),
+        ];
+        let (rows, _) = fetch_pages(&mut pager, &request("https://x.test/a"), &pages).unwrap();
+        assert_eq!(rows.len(), 3);
+    }
+
+    #[test]
+    fn offset_paging_skips_the_rows_fetched() {
+        let paging = Paging::Offset {
+            param: Cow::Borrowed("offset"),
+            limit_param: Cow::Borrowed("limit"),
+            size: 2,
+        };
+        let mut pager = Pager::new(paging, None, no_limits());
+        let pages = [
+            (
+                "https://x.test/a?limit=2&offset=0",
+                response(&[], json!({"rows": [1, 2]})),
+            ),
+            (
+                "https://x.test/a?limit=2&offset=2",
+                response(&[], json!({"rows": []})),
+            ),
+        ];
+        let (rows, urls) = fetch_pages(&mut pager, &request("https://x.test/a"), &pages).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(urls.len(), 2);
+    }
+
+    #[test]
+    fn next_url_paging_follows_the_url() {
+        let paging = Paging::NextUrl {
+            pointer: Cow::Borrowed("/next"),
+        };
+        let mut pager = Pager::new(paging, None, no_limits());
+        let pages = [
+            (
+                "https://x.test/a",
+                response(&[], json!({"rows": [1], "next": "https://x.test/b"})),
+            ),
+            (
+                "https://x.test/b",
+                response(&[], json!({"rows": [2], "next": null})),
+            ),
+        ];
+        let (rows, _) = fetch_pages(&mut pager, &request("https://x.test/a"), &pages).unwrap();
+        assert_eq!(rows, vec![json!(1), json!(2)]);
+    }
+
+    #[test]
+    fn limit_stops_paging_once_satisfied() {
+        let mut pager = Pager::new(Paging::LinkHeader, Some(2), no_limits());
+        let pages = [(
+            "https://x.test/a",
+            response(
+                &[("link", "<https://x.test/b>; rel=\"next\"")],
+                json!({"rows": [1, 2]}),
+            ),
+        )];
+        let (rows, urls) = fetch_pages(&mut pager, &request("https://x.test/a"), &pages).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(urls, vec!["https://x.test/a"]);
+        assert!(!pager.truncated());
+    }
+
+    #[test]
+    fn max_rows_fails_the_scan() {
+        let limits = Limits {
+            max_rows: Some(2),
+            ..no_limits()
+        };
+        let mut pager = Pager::new(Paging::None, None, limits);
+        let pages = [(
+            "https://x.test/a",
+            response(&[], json!({"rows": [1, 2, 3]})),
+        )];
+        let err = fetch_pages(&mut pager, &request("https://x.test/a"), &pages).unwrap_err();
+        assert!(err.contains("max_rows"), "{}", err);
+    }
+
+    #[test]
+    fn max_rows_allows_a_last_page_at_the_limit() {
+        let limits = Limits {
+            max_rows: Some(2),
+            ..no_limits()
+        };
+        let mut pager = Pager::new(Paging::None, None, limits);
+        let pages = [("https://x.test/a", response(&[], json!({"rows": [1, 2]})))];
+        let (rows, _) = fetch_pages(&mut pager, &request("https://x.test/a"), &pages).unwrap();
+        assert_eq!(rows.len(), 2);
+    }
+
+    #[test]
+    fn max_pages_fails_the_scan() {
+        let limits = Limits {
+            max_pages: 1,
+            ..no_limits()
+        };
+        let mut pager = Pager::new(Paging::LinkHeader, None, limits);
+        let pages = [(
+            "https://x.test/a",
+            response(
+                &[("link", "<https://x.test/b>; rel=\"next\"")],
+                json!({"rows": [1]}),
+            ),
+        )];
+        let err = fetch_pages(&mut pager, &request("https://x.test/a"), &pages).unwrap_err();
+        assert!(err.contains("max_pages"), "{}", err);
+    }
+
+    #[test]
+    fn max_response_bytes_fails_the_scan() {
+        let limits = Limits {
+            max_response_bytes: Some(8),
+            ..no_limits()
+        };
+        let mut pager = Pager::new(Paging::None, None, limits);
+        let pages = [(
+            "https://x.test/a",
+            response(&[], json!({"rows": [1, 2, 3]})),
+        )];
+        let err = fetch_pages(&mut pager, &request("https://x.test/a"), &pages).unwrap_err();
+        assert!(err.contains("max_response_bytes"), "{}", err);
+    }
+
+    #[test]
+    fn fetch_each_counts_each_request_as_a_page() {
+        let limits = Limits {
+            max_pages: 2,
+            ..no_limits()
+        };
+        let mut pager = Pager::new(Paging::None, None, limits);
+        let reqs = [request("https://x.test/1"), request("https://x.test/2")];
+        let rows = pager
+            .fetch_each(
+                &reqs,
+                |req| Ok(response(&[], json!(req.url))),
+                |resp| Ok(vec![serde_json::from_str(&resp.body).unwrap()]),
+            )
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![json!("https://x.test/1"), json!("https://x.test/2")]
+        );
+        assert_eq!(pager.pages(), 2);
+
+        let mut pager = Pager::new(
+            Paging::None,
+            None,
+            Limits {
+                max_pages: 1,
+                ..no_limits()
+            },
+        );
+        let err = pager
+            .fetch_each(
+                &reqs,
+                |_| Ok(response(&[], json!(null))),
+                |_| Ok(Vec::new()),
+            )
+            .unwrap_err();
+        assert!(err.contains("max_pages"), "{}", err);
+    }
+}
@@ -0,0 +1,178 @@
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+use crate::bindings::supabase::wrappers::{
+    http,
+    types::{Context, FdwError, FdwResult, Options, OptionsType, Row},
+    utils,
+};
+use crate::client::Client;
+use crate::debug::ScanPlan;
+use crate::incremental::Watermark;
+use crate::mapping::ColumnMap;
+use crate::pagination::{scan_limit, Limits, Pager, Paging};
+use crate::scan::Scan;
+use crate::template::{fill_template, Escape, Placeholders};
+use crate::Provider;
+
+// generic JSON REST provider, the request and the row mapping are taken from
+// options so a new API needs no code:
+//
+//   url          URL template, e.g. `{base_url}/v1/items?status={status}`
+//   method       HTTP method, defaults to GET
+//   body         request body template
+//   rows_pointer JSON pointer to the rows array in the response
+//   columns      column mappings, see `ColumnMap`
+//   paging       how to fetch the next page, see `Paging::from_options`
+//
+// `{name}` placeholders in templates are filled from equality quals on the
+// column of the same name, then table options, then server options.
+#[derive(Debug, Default)]
+pub(crate) struct RestFdw {
+    base_url: String,
+    client: Client,
+    headers: Vec<(String, String)>,
+    scan: Scan,
+}
+
+impl RestFdw {
+    pub(crate) fn new(ctx: &Context) -> Result<Self, FdwError> {
+        let opts = ctx.get_options(OptionsType::Server);
+
+        let mut headers = vec![
+            ("user-agent".to_owned(), "Wasm FDW".to_owned()),
+            ("accept".to_owned(), "application/json".to_owned()),
+            ("content-type".to_owned(), "application/json".to_owned()),
+        ];
+        // bearer token given directly or as a Vault secret id
+        let token = match opts.get("bearer_token_id") {
+            Some(secret_id) => Some(
+                utils::get_vault_secret(&secret_id)
+                    .ok_or(format!("cannot find vault secret {}", secret_id))?,
+            ),
+            None => opts.get("bearer_token"),
+        };
+        if let Some(token) = token {
+            headers.push(("authorization".to_owned(), format!("Bearer {}", token)));
+        }
+        headers.extend(parse_headers(&opts)?);
+
+        Ok(Self {
+            base_url: opts.require_or("base_url", ""),
+            client: Client::new(ctx)?,
+            headers,
+            ..Default::default()
+        })
+    }
+}
+
+impl Provider for RestFdw {
+    fn begin_scan(&mut self, ctx: &Context) -> FdwResult {
+        self.client.begin_scan(ctx)?;
+        let opts = ctx.get_options(OptionsType::Table);
+        let server_opts = ctx.get_options(OptionsType::Server);
+        let url_template = opts.require("url")?;
+        self.scan.watermark = Watermark::new(ctx, &format!("rest:{}", url_template))?;
+        let mut placeholders = Placeholders::new(ctx, vec![&opts, &server_opts])
+            .with("base_url", self.base_url.clone());
+        if let Some(watermark) = &self.scan.watermark {
+            placeholders = placeholders.with_value("watermark", watermark.since().to_owned());
+        }
+
+        let url = fill_template(&url_template, &mut placeholders, Escape::Url)?;
+        let body = match opts.get("body") {
+            Some(body) => fill_template(&body, &mut placeholders, Escape::Json)?,
+            None => String::new(),
+        };
+        let method = match opts.require_or("method", "GET").to_uppercase().as_str() {
+            "GET" => http::Method::Get,
+            "POST" => http::Method::Post,
+            "PUT" => http::Method::Put,
+            "PATCH" => http::Method::Patch,
+            "DELETE" => http::Method::Delete,
+            other => return Err(format!("unsupported method: {}", other)),
+        };
+        let mut headers = self.headers.clone();
+        headers.extend(parse_headers(&opts)?);
+
+        self.scan.columns = ColumnMap::parse(&opts.require_or("columns", ""))?;
+        for (col, value) in placeholders.used_quals() {
+            self.scan.columns.insert_fallback(col, value);
+        }
+
+        let first = http::Request {
+            method,
+            url,
+            headers,
+            body,
+        };
+        // an incremental scan reads everything after the watermark, so the
+        // watermark can't move past rows a LIMIT left unread
+        let limit = scan_limit(ctx).filter(|_| self.scan.watermark.is_none());
+        let mut pager = Pager::new(
+            Paging::from_options(&opts)?,
+            limit,
+            Limits::from_options(&opts, &server_opts)?,
+        );
+
+        let rows_pointer = opts.require_or("rows_pointer", "");
+        let rows = pager.fetch_all(
+            &first,
+            |req| self.client.fetch(req),
+            |resp| {
+                let mut resp_json: JsonValue =
+                    serde_json::from_str(&resp.body).map_err(|e| e.to_string())?;
+
+                // extract source rows from response, a single object is one row
+                let page = match resp_json.pointer_mut(&rows_pointer) {
+                    Some(JsonValue::Array(rows)) => std::mem::take(rows),
+                    Some(row @ JsonValue::Object(_)) => vec![row.take()],
+                    _ => {
+                        return Err(format!(
+                            "cannot get rows from response at '{}'",
+                            rows_pointer
+                        ))
+                    }
+                };
+                Ok((resp_json, page))
+            },
+        )?;
+
+        self.scan.plan = ScanPlan {
+            pushed: placeholders
+                .used_quals()
+                .iter()
+                .map(|(col, _)| col.clone())
+                .collect(),
+            limit_used: limit.is_some(),
+            pages: pager.pages(),
+        };
+        self.scan.set_rows(ctx, rows, pager.truncated())
+    }
+
+    fn iter_scan(&mut self, ctx: &Context, row: &Row) -> Result<Option<u32>, FdwError> {
+        self.scan.iter_scan(ctx, row, None)
+    }
+
+    fn re_scan(&mut self, ctx: &Context) -> FdwResult {
+        if !self.scan.rewind(ctx) {
+            return self.begin_scan(ctx);
+        }
+        Ok(())
+    }
+
+    fn end_scan(&mut self, _ctx: &Context) -> FdwResult {
+        self.scan.end_scan();
+        Ok(())
+    }
+}
+
+// extra request headers from the `headers` option, a JSON object
+fn parse_headers(opts: &Options) -> Result<Vec<(String, String)>, FdwError> {
+    let Some(headers) = opts.get("headers") else {
+        return Ok(Vec::new());
+    };
+    let headers: HashMap<String, String> = serde_json::from_str(&headers)
+        .map_err(|e| format!("invalid headers option, expect a JSON object: {}", e))?;
+    Ok(headers.into_iter().collect())
+}
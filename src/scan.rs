@@ -0,0 +1,94 @@
+use serde_json::Value as JsonValue;
+
+use crate::bindings::supabase::wrappers::types::{Context, FdwError, FdwResult, Row};
+use crate::debug::ScanPlan;
+use crate::has_param_quals;
+use crate::incremental::Watermark;
+use crate::mapping::{json_to_cell, ColumnMap};
+
+// rows of a scan and what the GitHub, REST and Square providers keep along
+// with them: the column mapping, the incremental watermark and the plan of
+// the request
+#[derive(Debug, Default)]
+pub(crate) struct Scan {
+    rows: Vec<JsonValue>,
+    idx: usize,
+    pub(crate) columns: ColumnMap,
+    pub(crate) watermark: Option<Watermark>,
+    pub(crate) plan: ScanPlan,
+}
+
+impl Scan {
+    // take the fetched rows. A scan stopped at a limit left rows out, so its
+    // watermark can't move past them.
+    pub(crate) fn set_rows(
+        &mut self,
+        ctx: &Context,
+        rows: Vec<JsonValue>,
+        truncated: bool,
+    ) -> FdwResult {
+        self.rows = rows;
+        self.idx = 0;
+        if truncated {
+            self.watermark = None;
+        }
+        if let Some(watermark) = &mut self.watermark {
+            for row in &self.rows {
+                watermark.observe(row, &self.columns)?;
+            }
+        }
+        self.plan.report(ctx, self.rows.len());
+        Ok(())
+    }
+
+    pub(crate) fn rows(&self) -> &[JsonValue] {
+        &self.rows
+    }
+
+    // push the next row's cells to Postgres, `write_only` is a column the
+    // API never returns
+    pub(crate) fn iter_scan(
+        &mut self,
+        ctx: &Context,
+        row: &Row,
+        write_only: Option<&str>,
+    ) -> Result<Option<u32>, FdwError> {
+        let Some(src_row) = self.rows.get(self.idx) else {
+            return Ok(None);
+        };
+        for tgt_col in ctx.get_columns() {
+            let tgt_col_name = tgt_col.name();
+            if write_only == Some(tgt_col_name.as_str()) {
+                row.push(None);
+                continue;
+            }
+            let cell = match self.columns.lookup(src_row, &tgt_col_name)? {
+                Some(src) => json_to_cell(src, tgt_col.type_oid())?,
+                None => None,
+            };
+            row.push(cell.as_ref());
+        }
+        self.idx += 1;
+        Ok(Some(0))
+    }
+
+    // start the rows over for a rescan, false when the rescan must fetch
+    // again, see `has_param_quals`
+    pub(crate) fn rewind(&mut self, ctx: &Context) -> bool {
+        if has_param_quals(ctx, &self.plan.pushed) {
+            return false;
+        }
+        self.idx = 0;
+        true
+    }
+
+    // move the watermark only when Postgres read all rows
+    pub(crate) fn end_scan(&mut self) {
+        if let Some(watermark) = &self.watermark {
+            if self.idx >= self.rows.len() {
+                watermark.save();
+            }
+        }
+        self.rows.clear();
+    }
+}
@@ -0,0 +1,147 @@
+use serde_json::Value as JsonValue;
+
+use crate::bindings::supabase::wrappers::{
+    http,
+    types::{Cell, Context, FdwError, FdwResult, OptionsType, Row, TypeOid},
+};
+use crate::client::Client;
+use crate::debug::ScanPlan;
+use crate::log;
+use crate::pagination::{Limits, Pager, Paging};
+use crate::Provider;
+
+// Google Sheets provider, reads a public sheet through the gviz query endpoint
+#[derive(Debug, Default)]
+pub(crate) struct SheetsFdw {
+    base_url: String,
+    client: Client,
+    src_rows: Vec<JsonValue>,
+    src_idx: usize,
+}
+
+impl SheetsFdw {
+    pub(crate) fn new(ctx: &Context) -> Result<Self, FdwError> {
+        // get API URL from foreign server options if it is specified
+        let opts = ctx.get_options(OptionsType::Server);
+        let base_url = opts.require_or("base_url", "https://docs.google.com/spreadsheets/d");
+
+        Ok(Self {
+            base_url,
+            client: Client::new(ctx)?,
+            ..Default::default()
+        })
+    }
+}
+
+impl Provider for SheetsFdw {
+    fn begin_scan(&mut self, ctx: &Context) -> FdwResult {
+        self.client.begin_scan(ctx)?;
+
+        // get sheet id from foreign table options and make the request URL
+        let opts = ctx.get_options(OptionsType::Table);
+        let sheet_id = opts.require("sheet_id")?;
+        let url = format!("{}/{}/gviz/tq?tqx=out:json", self.base_url, sheet_id);
+
+        // make up request headers
+        let headers: Vec<(String, String)> = vec![
+            ("user-agent".to_owned(), "Sheets FDW".to_owned()),
+            // header to make JSON response more cleaner
+            ("x-datasource-auth".to_owned(), "true".to_owned()),
+        ];
+
+        // make a request to Google API and parse response as JSON, the
+        // whole sheet comes in one response
+        let first = http::Request {
+            method: http::Method::Get,
+            url,
+            headers,
+            body: String::default(),
+        };
+        let limits = Limits::from_options(&opts, &ctx.get_options(OptionsType::Server))?;
+        let mut pager = Pager::new(Paging::None, None, limits);
+        self.src_rows = pager.fetch_all(
+            &first,
+            |req| self.client.fetch(req),
+            |resp| {
+                // remove invalid prefix from response to make a valid JSON string
+                let body = resp.body.strip_prefix(")]}'\n").ok_or("invalid response")?;
+                let resp_json: JsonValue = serde_json::from_str(body).map_err(|e| e.to_string())?;
+
+                // extract source rows from response
+                let rows = resp_json
+                    .pointer("/table/rows")
+                    .ok_or("cannot get rows from response")
+                    .map(|v| v.as_array().unwrap().to_owned())?;
+                Ok((resp_json, rows))
+            },
+        )?;
+        self.src_idx = 0;
+
+        // output a Postgres INFO to user at the `info` log level
+        log::info(&format!(
+            "We got response array length: {}",
+            self.src_rows.len()
+        ));
+        let plan = ScanPlan {
+            pages: pager.pages(),
+            ..Default::default()
+        };
+        plan.report(ctx, self.src_rows.len());
+
+        Ok(())
+    }
+
+    fn iter_scan(&mut self, ctx: &Context, row: &Row) -> Result<Option<u32>, FdwError> {
+        // if all source rows are consumed, stop data scan
+        if self.src_idx >= self.src_rows.len() {
+            return Ok(None);
+        }
+
+        // extract current source row, an example of the source row in JSON:
+        // {
+        //   "c": [{
+        //      "v": 1.0,
+        //      "f": "1"
+        //    }, {
+        //      "v": "Erlich Bachman"
+        //    }, null, null, null, null, { "v": null }
+        //    ]
+        // }
+        let src_row = &self.src_rows[self.src_idx];
+
+        // loop through each target column, map source cell to target cell
+        for tgt_col in ctx.get_columns() {
+            let (tgt_col_num, tgt_col_name) = (tgt_col.num(), tgt_col.name());
+            if let Some(src) = src_row.pointer(&format!("/c/{}/v", tgt_col_num - 1)) {
+                // we only support I64 and String cell types here, add more type
+                // conversions if you need
+                let cell = match tgt_col.type_oid() {
+                    TypeOid::I64 => src.as_f64().map(|v| Cell::I64(v as _)),
+                    TypeOid::String => src.as_str().map(|v| Cell::String(v.to_owned())),
+                    _ => {
+                        return Err(format!(
+                            "column {} data type is not supported",
+                            tgt_col_name
+                        ));
+                    }
+                };
+
+                // push the cell to target row
+                row.push(cell.as_ref());
+            } else {
+                row.push(None);
+            }
+        }
+
+        // advance to next source row
+        self.src_idx += 1;
+
+        // tell Postgres we've done one row, and need to scan the next row
+        Ok(Some(0))
+    }
+
+    fn end_scan(&mut self, _ctx: &Context) -> FdwResult {
+        self.src_rows.clear();
+        Ok(())
+    }
+}
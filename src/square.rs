@@ -0,0 +1,822 @@
+mod actions;
+mod objects;
+mod search;
+
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+use std::collections::HashMap;
+
+use crate::bindings::supabase::wrappers::{
+    http, time,
+    types::{Cell, Context, FdwError, FdwResult, OptionsType, Row},
+};
+use crate::client::{header, Client};
+use crate::debug::ScanPlan;
+use crate::incremental::Watermark;
+use crate::log;
+use crate::mapping::ColumnMap;
+use crate::pagination::{scan_limit, Limits, Pager, Paging};
+use crate::scan::Scan;
+use crate::{url_encode, Provider};
+use objects::{find_object, BatchRows, DeleteVersion, Endpoint, Nested, Since, SquareObject};
+use search::{id_quals, pushdown_quals, PushdownQual};
+
+// Square provider, see https://developer.squareup.com/reference/square
+#[derive(Debug, Default)]
+pub(crate) struct SquareFdw {
+    base_url: String,
+    client: Client,
+    access_token: String,
+    square_version: String,
+    object: String,
+    location_id: Option<String>,
+    // rows, scanned column to Square field mapping from table options, and
+    // watermark of the scan
+    scan: Scan,
+
+    // column holding the object version, from table options
+    version_column: String,
+    // column holding user supplied idempotency keys, from table options
+    idempotency_key_column: Option<String>,
+    // modify context used to derive idempotency keys
+    modify_epoch: i64,
+    modify_seq: u64,
+}
+
+// object versions seen by scans, keyed by object type and id. They are kept
+// across FDW instances, so an update or delete following a scan can send
+// the version back to Square.
+static mut VERSIONS: *mut HashMap<(String, String), i64> =
+    std::ptr::null_mut::<HashMap<(String, String), i64>>();
+
+fn versions() -> &'static mut HashMap<(String, String), i64> {
+    unsafe {
+        if VERSIONS.is_null() {
+            VERSIONS = Box::leak(Box::default());
+        }
+        &mut (*VERSIONS)
+    }
+}
+
+// API version sent when the `square_version` server option is not set, see
+// https://developer.squareup.com/docs/build-basics/versioning-overview
+const DEFAULT_SQUARE_VERSION: &str = "2024-10-17";
+
+// error codes Square returns when the requested API version is not usable
+const VERSION_ERROR_CODES: &[&str] = &["API_VERSION_INCOMPATIBLE", "INVALID_SQUARE_VERSION_FORMAT"];
+
+impl SquareFdw {
+    pub(crate) fn new(ctx: &Context) -> Result<Self, FdwError> {
+        // Retrieve server options (e.g., access token and base URL)
+        let server_opts = ctx.get_options(OptionsType::Server);
+
+        // the environment picks the base URL, `api_url` overrides it
+        let default_url = match server_opts.require_or("environment", "production").as_str() {
+            "production" => "https://connect.squareup.com/v2",
+            "sandbox" => "https://connect.squareupsandbox.com/v2",
+            other => {
+                return Err(format!(
+                    "Unknown environment: {}, expected production or sandbox",
+                    other
+                ))
+            }
+        };
+
+        Ok(Self {
+            base_url: server_opts.require_or("api_url", default_url),
+            client: Client::new(ctx)?,
+            access_token: server_opts.require("access_token")?,
+            square_version: server_opts.require_or("square_version", DEFAULT_SQUARE_VERSION),
+            ..Default::default()
+        })
+    }
+
+    fn headers(&self) -> Vec<(String, String)> {
+        vec![
+            (
+                "Authorization".to_owned(),
+                format!("Bearer {}", self.access_token),
+            ),
+            ("Content-Type".to_owned(), "application/json".to_owned()),
+            ("Accept".to_owned(), "application/json".to_owned()),
+            ("Square-Version".to_owned(), self.square_version.clone()),
+        ]
+    }
+
+    // send a request to Square and warn about API version problems in the
+    // response, the response itself is returned unchanged
+    fn send(&self, req: &http::Request) -> http::HttpResult {
+        let resp = self.client.send(req)?;
+
+        if let Some(deprecation) = header(&resp, "deprecation") {
+            log::warning(&format!(
+                "Square API version {} used by {} is deprecated ({}), sunset: {}",
+                self.square_version,
+                self.object,
+                deprecation,
+                header(&resp, "sunset").unwrap_or("unknown")
+            ));
+        }
+
+        if resp.status_code >= 400 {
+            let resp_json: JsonValue = serde_json::from_str(&resp.body).unwrap_or_default();
+            let errors = resp_json["errors"].as_array().into_iter().flatten();
+            for e in errors.filter(|e| {
+                e["code"]
+                    .as_str()
+                    .is_some_and(|code| VERSION_ERROR_CODES.contains(&code))
+            }) {
+                log::warning(&format!(
+                    "Square rejected API version {}, check the square_version server option: {}",
+                    self.square_version,
+                    e["detail"].as_str().unwrap_or_default()
+                ));
+            }
+        }
+
+        Ok(resp)
+    }
+
+    // Square applies a create or update call only once per idempotency key,
+    // so a retried request cannot charge a customer twice. The key is taken
+    // from the `idempotency_key_column` column if it is set, otherwise it is
+    // derived from the request and the current modify, so it stays the same
+    // when the request is resent.
+    fn idempotency_key(
+        &mut self,
+        id: Option<&str>,
+        body: &mut JsonMap<String, JsonValue>,
+    ) -> String {
+        if let Some(col) = &self.idempotency_key_column {
+            match body.remove(col) {
+                Some(JsonValue::String(key)) => return key,
+                Some(JsonValue::Null) | None => {}
+                Some(key) => return key.to_string(),
+            }
+        }
+
+        // the row sequence keeps identical rows in one statement apart
+        self.modify_seq += 1;
+        let seed = format!(
+            "{}|{}|{}|{}|{}",
+            self.object,
+            id.unwrap_or_default(),
+            self.modify_epoch,
+            self.modify_seq,
+            JsonValue::Object(body.clone())
+        );
+        derive_idempotency_key(&seed)
+    }
+
+    fn set_table_options(&mut self, ctx: &Context) -> FdwResult {
+        let table_opts = ctx.get_options(OptionsType::Table);
+        self.object = table_opts.require("object")?;
+        self.location_id = table_opts.get("location_id");
+        self.version_column = table_opts.require_or("version_column", "version");
+        self.idempotency_key_column = table_opts.get("idempotency_key_column");
+        // the version column can be named differently from Square's field
+        self.scan.columns = ColumnMap::parse(&table_opts.require_or("columns", ""))?;
+        self.scan
+            .columns
+            .insert_default(&self.version_column, "version");
+        Ok(())
+    }
+
+    fn endpoint_url(&self, endpoint: &Endpoint, id: &str) -> String {
+        format!(
+            "{}/{}",
+            self.base_url,
+            endpoint.path.replace("{id}", &url_encode(id))
+        )
+    }
+
+    // fetch the rows of the object, following the cursor until all pages or
+    // `limit` rows are fetched. The rows of a nested object are the elements
+    // of its parents, which is what limits count. Also returns whether a
+    // limit stopped the scan.
+    fn list_rows(
+        &mut self,
+        ctx: &Context,
+        quals: &[PushdownQual],
+        obj: &SquareObject,
+        nested: Option<&Nested>,
+        limit: Option<u64>,
+    ) -> Result<(Vec<JsonValue>, bool), FdwError> {
+        let (first, pushed) = self.list_request(ctx, quals, obj)?;
+        let mut pager = Pager::new(obj.paging.clone(), limit, scan_limits(ctx)?);
+
+        let rows = pager.fetch_all(
+            &first,
+            |req| {
+                let resp = self.send(req)?;
+                if resp.status_code < 200 || resp.status_code >= 300 {
+                    return Err(square_error("scan", &resp, None));
+                }
+                Ok(resp)
+            },
+            |resp| {
+                let mut resp_json: JsonValue =
+                    serde_json::from_str(&resp.body).map_err(|e| e.to_string())?;
+                let page = match resp_json[obj.array_key].as_array_mut() {
+                    Some(page) => std::mem::take(page),
+                    None => Vec::new(),
+                };
+                let page = match nested {
+                    Some(nested) => flatten_rows(nested, &page),
+                    None => page,
+                };
+                Ok((resp_json, page))
+            },
+        )?;
+        self.scan.plan.pushed = pushed;
+        self.scan.plan.limit_used = limit.is_some();
+        self.scan.plan.pages = pager.pages();
+        Ok((rows, pager.truncated()))
+    }
+
+    // fetch objects by id, with one batch request if the object has a batch
+    // endpoint, or one GET per id otherwise, each GET counts as a page.
+    // Returns None if the object can only be listed, or the rows and
+    // whether a limit stopped the scan.
+    fn retrieve_rows(
+        &mut self,
+        ctx: &Context,
+        obj: &SquareObject,
+        ids: &[String],
+    ) -> Result<Option<(Vec<JsonValue>, bool)>, FdwError> {
+        let mut pager = Pager::new(Paging::None, None, scan_limits(ctx)?);
+        let fetch = |req: &http::Request| {
+            let resp = self.send(req)?;
+            // an unknown id is just no row
+            if resp.status_code != 404 && (resp.status_code < 200 || resp.status_code >= 300) {
+                return Err(square_error("scan", &resp, None));
+            }
+            Ok(resp)
+        };
+
+        let rows = if let (Some(batch), true) = (&obj.batch, ids.len() > 1) {
+            let mut body = JsonMap::new();
+            body.insert(batch.ids_field.to_owned(), json!(ids));
+            let req = http::Request {
+                method: batch.endpoint.method,
+                url: self.endpoint_url(&batch.endpoint, ""),
+                headers: self.headers(),
+                body: JsonValue::Object(body).to_string(),
+            };
+            pager.fetch_all(&req, fetch, |resp| {
+                let resp_json: JsonValue =
+                    serde_json::from_str(&resp.body).map_err(|e| e.to_string())?;
+                let rows = match batch.rows {
+                    BatchRows::Array(key) => resp_json[key].as_array().cloned().unwrap_or_default(),
+                    BatchRows::Map(key, item_key) => resp_json[key]
+                        .as_object()
+                        .into_iter()
+                        .flat_map(|v| v.values())
+                        .filter_map(|v| v.get(item_key).cloned())
+                        .collect(),
+                };
+                Ok((JsonValue::Null, rows))
+            })?
+        } else {
+            let Some(endpoint) = &obj.get else {
+                return Ok(None);
+            };
+            let reqs: Vec<http::Request> = ids
+                .iter()
+                .map(|id| http::Request {
+                    method: endpoint.method,
+                    url: self.endpoint_url(endpoint, id),
+                    headers: self.headers(),
+                    body: String::new(),
+                })
+                .collect();
+            pager.fetch_each(&reqs, fetch, |resp| {
+                if resp.status_code == 404 {
+                    return Ok(Vec::new());
+                }
+                let mut resp_json: JsonValue =
+                    serde_json::from_str(&resp.body).map_err(|e| e.to_string())?;
+                Ok(vec![resp_json[obj.object_key].take()])
+            })?
+        };
+        self.scan.plan.pages = pager.pages();
+
+        // keep the rows a listing of the table would have returned
+        let location_id = self.location_id.as_deref().filter(|_| obj.location_scoped);
+        let rows = rows
+            .into_iter()
+            .filter(|row| location_id.is_none_or(|id| row["location_id"] == id))
+            .filter(|row| obj.scope.is_none_or(|scope| scope(ctx, row)))
+            .collect();
+        Ok(Some((rows, pager.truncated())))
+    }
+
+    // make the list request for the first page of the object, along with
+    // the columns of the quals it sends
+    fn list_request(
+        &self,
+        ctx: &Context,
+        quals: &[PushdownQual],
+        obj: &SquareObject,
+    ) -> Result<(http::Request, Vec<String>), FdwError> {
+        let mut query: Vec<String> = Vec::new();
+        let mut body: JsonValue = serde_json::from_str(obj.list_body).map_err(|e| e.to_string())?;
+        // set before the search, so a qual on the update time takes precedence
+        if let (Some(since), Some(watermark)) = (obj.since, &self.scan.watermark) {
+            match since {
+                Since::Query(param) => {
+                    query.push(format!("{}={}", param, url_encode(watermark.since())))
+                }
+                Since::Body(field) => body[field] = json!(watermark.since()),
+            }
+        }
+        let pushed = match obj.search {
+            Some(search) => search(ctx, quals, &mut body)?,
+            None => Vec::new(),
+        };
+
+        if obj.location_scoped {
+            let location_id = self.location_id.as_deref().ok_or(format!(
+                "location_id table option is required for object type: {}",
+                obj.name
+            ))?;
+            match obj.list.method {
+                http::Method::Get => query.push(format!("location_id={}", url_encode(location_id))),
+                _ => body["location_ids"] = json!([location_id]),
+            }
+        }
+
+        let mut url = self.endpoint_url(&obj.list, "");
+        if !query.is_empty() {
+            url = format!("{}?{}", url, query.join("&"));
+        }
+        let body = match obj.list.method {
+            http::Method::Get => String::new(),
+            _ => body.to_string(),
+        };
+
+        let req = http::Request {
+            method: obj.list.method,
+            url,
+            headers: self.headers(),
+            body,
+        };
+        Ok((req, pushed))
+    }
+
+    // remember the version of each scanned object, replacing the ones from
+    // earlier scans of the same object type
+    fn remember_versions(&self) {
+        let versions = versions();
+        versions.retain(|(object, _), _| object != &self.object);
+        for src_row in self.scan.rows() {
+            if let (Some(id), Some(version)) = (src_row["id"].as_str(), src_row["version"].as_i64())
+            {
+                versions.insert((self.object.clone(), id.to_owned()), version);
+            }
+        }
+    }
+
+    fn known_version(&self, id: &str) -> Option<i64> {
+        versions()
+            .get(&(self.object.clone(), id.to_owned()))
+            .copied()
+    }
+
+    // take the version for an update from the row, or from the last scan if
+    // the row does not have it
+    fn take_version(&self, id: &str, body: &mut JsonMap<String, JsonValue>) -> Option<i64> {
+        body.remove(&self.version_column)
+            .and_then(|v| v.as_i64())
+            .or_else(|| self.known_version(id))
+    }
+}
+
+impl Provider for SquareFdw {
+    fn begin_scan(&mut self, ctx: &Context) -> FdwResult {
+        // Retrieve table options (e.g., object type)
+        self.set_table_options(ctx)?;
+        self.client.begin_scan(ctx)?;
+        let obj = find_object(&self.object)?;
+        // nested objects are listed through their parent
+        let list_obj = match &obj.nested {
+            Some(nested) => find_object(nested.parent)?,
+            None => obj,
+        };
+        // quals on a nested object are about the elements, not the parent
+        let (quals, ids) = match &obj.nested {
+            Some(_) => (Vec::new(), None),
+            None => {
+                let quals = pushdown_quals(ctx);
+                let ids = id_quals(&quals);
+                (quals, ids)
+            }
+        };
+
+        // incremental scans list what changed after the watermark
+        self.scan.watermark = match ids {
+            Some(_) => None,
+            None => {
+                let key = match &self.location_id {
+                    Some(location_id) => format!("square:{}:{}", self.object, location_id),
+                    None => format!("square:{}", self.object),
+                };
+                Watermark::new(ctx, &key)?
+            }
+        };
+        if self.scan.watermark.is_some() && (obj.since.is_none() || obj.nested.is_some()) {
+            return Err(format!(
+                "Incremental scans are not supported for object type: {}",
+                self.object
+            ));
+        }
+
+        // point lookups by id are fetched directly instead of listing everything
+        self.scan.plan = ScanPlan::default();
+        let rows = match ids {
+            Some(ids) => {
+                self.scan.plan.pushed = vec!["id".to_owned()];
+                self.retrieve_rows(ctx, obj, &ids)?
+            }
+            None => None,
+        };
+        let (mut rows, truncated) = match rows {
+            Some(rows) => rows,
+            None => {
+                let limit = scan_limit(ctx).filter(|_| self.scan.watermark.is_none());
+                self.list_rows(ctx, &quals, list_obj, obj.nested.as_ref(), limit)?
+            }
+        };
+        if let Some(normalize) = obj.normalize {
+            rows.iter_mut().for_each(normalize);
+        }
+
+        log::info(&format!(
+            "Retrieved {} records for {}",
+            rows.len(),
+            self.object
+        ));
+        self.scan.set_rows(ctx, rows, truncated)?;
+        self.remember_versions();
+
+        Ok(())
+    }
+
+    fn iter_scan(&mut self, ctx: &Context, row: &Row) -> Result<Option<u32>, FdwError> {
+        // idempotency keys are write-only, Square never returns them
+        self.scan
+            .iter_scan(ctx, row, self.idempotency_key_column.as_deref())
+    }
+
+    fn re_scan(&mut self, ctx: &Context) -> FdwResult {
+        if !self.scan.rewind(ctx) {
+            return self.begin_scan(ctx);
+        }
+        Ok(())
+    }
+
+    fn end_scan(&mut self, _ctx: &Context) -> FdwResult {
+        self.scan.end_scan();
+        Ok(())
+    }
+
+    fn begin_modify(&mut self, ctx: &Context) -> FdwResult {
+        self.set_table_options(ctx)?;
+        self.client.begin_modify();
+
+        self.modify_epoch = time::epoch_secs();
+        self.modify_seq = 0;
+
+        Ok(())
+    }
+
+    fn insert(&mut self, _ctx: &Context, row: &Row) -> FdwResult {
+        let obj = find_object(&self.object)?;
+        let endpoint = obj.insert.ok_or(format!(
+            "Insert not supported for object type: {}",
+            obj.name
+        ))?;
+
+        let mut fields = build_body_json(row)?;
+        let idempotency_key = self.idempotency_key(None, &mut fields);
+        // a top-level location defaults to the `location_id` table option
+        if let (true, Some(location_id)) = (
+            obj.top_level_fields.contains(&"location_id"),
+            &self.location_id,
+        ) {
+            if fields.get("location_id").is_none_or(|v| v.is_null()) {
+                fields.insert("location_id".to_owned(), location_id.clone().into());
+            }
+        }
+        if let Some(prepare) = obj.prepare_insert {
+            fields = prepare(fields)?;
+        }
+
+        let req = http::Request {
+            method: endpoint.method,
+            url: self.endpoint_url(&endpoint, ""),
+            headers: self.headers(),
+            body: request_body(obj, fields, &idempotency_key),
+        };
+
+        let resp = self.send(&req)?;
+        if resp.status_code >= 200 && resp.status_code < 300 {
+            Ok(())
+        } else {
+            Err(square_error("insert", &resp, Some(&idempotency_key)))
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context, rowid: Cell, row: &Row) -> FdwResult {
+        let id = match rowid {
+            Cell::String(s) => s,
+            Cell::I32(i) => i.to_string(),
+            Cell::I64(i) => i.to_string(),
+            _ => return Err("Invalid rowid type".to_owned()),
+        };
+
+        let obj = find_object(&self.object)?;
+        let endpoint = obj.update.ok_or(format!(
+            "Update not supported for object type: {}",
+            obj.name
+        ))?;
+
+        let mut fields = build_body_json(row)?;
+        let idempotency_key = self.idempotency_key(Some(&id), &mut fields);
+        // Square rejects the update if the object changed since this version
+        let version = self.take_version(&id, &mut fields);
+        if let Some(prepare) = obj.prepare_update {
+            fields = prepare(fields)?;
+        }
+        if obj.id_in_body {
+            fields.insert("id".to_owned(), JsonValue::String(id.clone()));
+        }
+        if let Some(version) = version {
+            fields.insert("version".to_owned(), version.into());
+        }
+
+        let req = http::Request {
+            method: endpoint.method,
+            url: self.endpoint_url(&endpoint, &id),
+            headers: self.headers(),
+            body: request_body(obj, fields, &idempotency_key),
+        };
+
+        let resp = self.send(&req)?;
+        if resp.status_code >= 200 && resp.status_code < 300 {
+            // keep the new version, so the object can be updated again
+            let resp_json: JsonValue =
+                serde_json::from_str(&resp.body).map_err(|e| e.to_string())?;
+            let updated = resp_json
+                .as_object()
+                .and_then(|v| v.values().find(|v| v["id"] == id.as_str()));
+            if let Some(version) = updated.and_then(|v| v["version"].as_i64()) {
+                versions().insert((self.object.clone(), id), version);
+            }
+            Ok(())
+        } else {
+            Err(square_error("update", &resp, Some(&idempotency_key)))
+        }
+    }
+
+    fn delete(&mut self, _ctx: &Context, rowid: Cell) -> FdwResult {
+        let id = match rowid {
+            Cell::String(s) => s,
+            Cell::I32(i) => i.to_string(),
+            Cell::I64(i) => i.to_string(),
+            _ => return Err("Invalid rowid type".to_owned()),
+        };
+
+        let obj = find_object(&self.object)?;
+        let endpoint = obj.delete.ok_or(format!(
+            "Delete not supported for object type: {}",
+            obj.name
+        ))?;
+
+        let mut url = self.endpoint_url(&endpoint, &id);
+        let mut body = match endpoint.method {
+            http::Method::Get | http::Method::Delete => String::new(),
+            _ => "{}".to_owned(),
+        };
+        let version = self.known_version(&id);
+        match obj.delete_version {
+            DeleteVersion::None => {}
+            DeleteVersion::Query => {
+                if let Some(version) = version {
+                    url.push_str(&format!("?version={}", version));
+                }
+            }
+            DeleteVersion::Body => {
+                let version = version.ok_or(format!(
+                    "Cannot delete {} {}: its version is unknown, scan it first",
+                    obj.name, id
+                ))?;
+                body = format!("{{\"version\": {}}}", version);
+            }
+        }
+
+        let req = http::Request {
+            method: endpoint.method,
+            url,
+            headers: self.headers(),
+            body,
+        };
+
+        let resp = self.send(&req)?;
+        if resp.status_code >= 200 && resp.status_code < 300 {
+            Ok(())
+        } else {
+            Err(square_error("delete", &resp, None))
+        }
+    }
+}
+
+// limits of a scan from the table and server options
+fn scan_limits(ctx: &Context) -> Result<Limits, FdwError> {
+    Limits::from_options(
+        &ctx.get_options(OptionsType::Table),
+        &ctx.get_options(OptionsType::Server),
+    )
+}
+
+// make one row per element of the nested array in each parent row, with the
+// parent id attached, e.g. the line items of orders
+fn flatten_rows(nested: &Nested, parents: &[JsonValue]) -> Vec<JsonValue> {
+    let mut rows = Vec::new();
+    for parent in parents {
+        let children = parent.pointer(nested.pointer).and_then(|v| v.as_array());
+        for child in children.into_iter().flatten() {
+            let mut child = child.clone();
+            if let Some(fields) = child.as_object_mut() {
+                fields
+                    .entry(nested.parent_id_field)
+                    .or_insert_with(|| parent["id"].clone());
+            }
+            rows.push(child);
+        }
+    }
+    rows
+}
+
+// wrap the row fields in the create or update request body of the object
+fn request_body(
+    obj: &SquareObject,
+    fields: JsonMap<String, JsonValue>,
+    idempotency_key: &str,
+) -> String {
+    let mut body = match obj.body_key {
+        Some(key) => {
+            let mut fields = fields;
+            let mut body = JsonMap::new();
+            for field in obj.top_level_fields {
+                if let Some(value) = fields.remove(*field) {
+                    body.insert(field.to_string(), value);
+                }
+            }
+            body.insert(key.to_owned(), JsonValue::Object(fields));
+            body
+        }
+        None => fields,
+    };
+    body.insert("idempotency_key".to_owned(), idempotency_key.into());
+    JsonValue::Object(body).to_string()
+}
+
+// Helper function to build JSON body from row data
+fn build_body_json(row: &Row) -> Result<JsonMap<String, JsonValue>, FdwError> {
+    let mut body_json = JsonMap::new();
+
+    for (col_name, cell) in row.cols().into_iter().zip(row.cells()) {
+        let value = match cell {
+            Some(Cell::Bool(b)) => JsonValue::Bool(b),
+            Some(Cell::String(s)) => JsonValue::String(s),
+            Some(Cell::I32(i)) => JsonValue::Number(i.into()),
+            Some(Cell::I64(i)) => JsonValue::Number(i.into()),
+            Some(Cell::F64(f)) => serde_json::Number::from_f64(f)
+                .map(JsonValue::Number)
+                .unwrap_or(JsonValue::Null),
+            Some(Cell::Json(s)) => serde_json::from_str(&s).map_err(|e| e.to_string())?,
+            Some(Cell::Timestamp(ts)) => JsonValue::String(time::epoch_ms_to_rfc3339(ts)?),
+            _ => JsonValue::Null,
+        };
+        body_json.insert(col_name, value);
+    }
+    Ok(body_json)
+}
+
+// 64-bit FNV-1a hash, stable across builds unlike the std hasher
+fn fnv1a(data: &[u8], basis: u64) -> u64 {
+    data.iter().fold(basis, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// format a 128-bit hash of the seed as a UUID, which fits every Square
+// idempotency key length limit
+fn derive_idempotency_key(seed: &str) -> String {
+    let hi = fnv1a(seed.as_bytes(), 0xcbf2_9ce4_8422_2325);
+    let lo = fnv1a(seed.as_bytes(), hi);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        hi >> 32,
+        (hi >> 16) & 0xffff,
+        hi & 0xffff,
+        lo >> 48,
+        lo & 0xffff_ffff_ffff
+    )
+}
+
+// make an error message from a Square error response, the body looks like:
+// {
+//   "errors": [{
+//     "category": "INVALID_REQUEST_ERROR",
+//     "code": "IDEMPOTENCY_KEY_REUSED",
+//     "detail": "...",
+//     "field": "..."
+//   }]
+// }
+fn square_error(action: &str, resp: &http::Response, idempotency_key: Option<&str>) -> FdwError {
+    let errors = serde_json::from_str::<JsonValue>(&resp.body)
+        .ok()
+        .and_then(|v| v["errors"].as_array().cloned())
+        .unwrap_or_default();
+    if errors.is_empty() {
+        return format!("Failed to {}: {}", action, resp.body);
+    }
+
+    // report a version conflict like Postgres reports a serialization failure
+    if let Some(e) = errors.iter().find(|e| e["code"] == "VERSION_MISMATCH") {
+        return format!(
+            "could not serialize access due to concurrent update: the Square object was \
+             changed since it was read, re-read it and retry the {} ({})",
+            action,
+            e["detail"].as_str().unwrap_or_default()
+        );
+    }
+
+    if let Some(key) = idempotency_key {
+        if errors.iter().any(|e| e["code"] == "IDEMPOTENCY_KEY_REUSED") {
+            return format!(
+                "Failed to {}: idempotency key '{}' was already used for a different request, \
+                 use a new key or resend the original request unchanged",
+                action, key
+            );
+        }
+    }
+
+    let details = errors
+        .iter()
+        .map(|e| {
+            let mut detail = format!(
+                "{}: {}",
+                e["code"].as_str().unwrap_or("UNKNOWN"),
+                e["detail"].as_str().unwrap_or_default()
+            );
+            if let Some(field) = e["field"].as_str() {
+                detail.push_str(&format!(" (field: {})", field));
+            }
+            detail
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    format!("Failed to {}: {}", action, details)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_the_reference_values() {
+        let basis = 0xcbf2_9ce4_8422_2325;
+        assert_eq!(fnv1a(b"", basis), basis);
+        assert_eq!(fnv1a(b"a", basis), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar", basis), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn gift_card_bodies_keep_the_location_at_the_top_level() {
+        let obj = find_object("gift_cards").unwrap();
+        let fields = json!({"location_id": "L1", "type": "DIGITAL"});
+        let body = request_body(obj, fields.as_object().unwrap().clone(), "k1");
+        let body: JsonValue = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "idempotency_key": "k1",
+                "location_id": "L1",
+                "gift_card": {"type": "DIGITAL"}
+            })
+        );
+    }
+
+    #[test]
+    fn idempotency_keys_are_stable_uuids() {
+        let key = derive_idempotency_key("payments|P1|1700000000|1|{}");
+        assert_eq!(key, derive_idempotency_key("payments|P1|1700000000|1|{}"));
+        assert_ne!(key, derive_idempotency_key("payments|P1|1700000000|2|{}"));
+
+        let groups: Vec<usize> = key.split('-').map(str::len).collect();
+        assert_eq!(groups, vec![8, 4, 4, 4, 12]);
+        assert!(key.chars().all(|c| c == '-' || c.is_ascii_hexdigit()));
+    }
+}
@@ -0,0 +1,88 @@
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::bindings::supabase::wrappers::{time, types::FdwError};
+
+// turns the fields of a written row into the request fields of a business
+// action endpoint, rejecting rows the action cannot be done with
+pub(super) type PrepareFn =
+    fn(JsonMap<String, JsonValue>) -> Result<JsonMap<String, JsonValue>, FdwError>;
+
+// INSERT into `refunds` refunds a payment, see
+// https://developer.squareup.com/reference/square/refunds-api/refund-payment
+pub(super) fn refund_payment(
+    fields: JsonMap<String, JsonValue>,
+) -> Result<JsonMap<String, JsonValue>, FdwError> {
+    for required in ["payment_id", "amount_money"] {
+        if fields.get(required).is_none_or(|v| v.is_null()) {
+            return Err(format!(
+                "Cannot refund a payment without {}, set payment_id and amount_money",
+                required
+            ));
+        }
+    }
+    Ok(fields)
+}
+
+// UPDATE on `payments` setting the status to COMPLETED completes an approved
+// payment, no other update is possible, see
+// https://developer.squareup.com/reference/square/payments-api/complete-payment
+pub(super) fn complete_payment(
+    fields: JsonMap<String, JsonValue>,
+) -> Result<JsonMap<String, JsonValue>, FdwError> {
+    match fields.get("status").and_then(|v| v.as_str()) {
+        Some("COMPLETED") => Ok(JsonMap::new()),
+        _ => Err(
+            "Update on payments only supports setting status to 'COMPLETED', \
+             which completes an approved payment"
+                .to_owned(),
+        ),
+    }
+}
+
+// INSERT into `inventory_changes` records a physical count or an adjustment.
+// The row's `type` column picks the change type and the other columns are
+// its fields, e.g. `catalog_object_id`, `location_id`, `quantity`, `state`
+// for counts and `from_state`, `to_state` for adjustments, see
+// https://developer.squareup.com/reference/square/inventory-api/batch-change-inventory
+pub(super) fn inventory_change(
+    mut fields: JsonMap<String, JsonValue>,
+) -> Result<JsonMap<String, JsonValue>, FdwError> {
+    let change_type = fields
+        .remove("type")
+        .and_then(|v| v.as_str().map(|v| v.to_uppercase()))
+        .unwrap_or_default();
+    let change_key = match change_type.as_str() {
+        "PHYSICAL_COUNT" => "physical_count",
+        "ADJUSTMENT" => "adjustment",
+        _ => {
+            return Err("Inventory change type must be 'PHYSICAL_COUNT' or 'ADJUSTMENT'".to_owned())
+        }
+    };
+    for required in ["catalog_object_id", "location_id", "quantity"] {
+        if fields.get(required).is_none_or(|v| v.is_null()) {
+            return Err(format!("Inventory change requires {}", required));
+        }
+    }
+
+    // Square takes quantities as decimal strings
+    if let Some(quantity) = fields.get_mut("quantity") {
+        if let JsonValue::Number(n) = quantity {
+            *quantity = JsonValue::String(n.to_string());
+        }
+    }
+    if fields.get("occurred_at").is_none_or(|v| v.is_null()) {
+        let now = time::epoch_ms_to_rfc3339(time::epoch_secs() * 1_000_000)?;
+        fields.insert("occurred_at".to_owned(), JsonValue::String(now));
+    }
+
+    let mut change = JsonMap::new();
+    change.insert("type".to_owned(), JsonValue::String(change_type));
+    change.insert(change_key.to_owned(), JsonValue::Object(fields));
+
+    let mut body = JsonMap::new();
+    body.insert(
+        "changes".to_owned(),
+        JsonValue::Array(vec![JsonValue::Object(change)]),
+    );
+    Ok(body)
+}
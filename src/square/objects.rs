@@ -0,0 +1,425 @@
+use serde_json::Value as JsonValue;
+use std::borrow::Cow;
+
+use super::actions::{complete_payment, inventory_change, refund_payment, PrepareFn};
+use super::search::{
+    catalog_name, catalog_scope, catalog_search, inventory_counts_search, ScopeFn, SearchFn,
+};
+use crate::bindings::supabase::wrappers::{http::Method, types::FdwError};
+use crate::pagination::{Paging, ParamIn};
+
+// `cursor` query parameter, used by GET list endpoints
+const QUERY_CURSOR: Paging = Paging::Cursor {
+    pointer: Cow::Borrowed("/cursor"),
+    param: Cow::Borrowed("cursor"),
+    param_in: ParamIn::Query,
+};
+
+// `cursor` field in the request body, used by POST search endpoints
+const BODY_CURSOR: Paging = Paging::Cursor {
+    pointer: Cow::Borrowed("/cursor"),
+    param: Cow::Borrowed("cursor"),
+    param_in: ParamIn::Body,
+};
+
+// where a list request takes the time to return objects updated after,
+// used by incremental scans
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Since {
+    // query parameter of a GET list endpoint
+    Query(&'static str),
+    // field of a search request body
+    Body(&'static str),
+}
+
+// where a delete call sends the object version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum DeleteVersion {
+    None,
+    // `version` query parameter, sent when the version is known
+    Query,
+    // `version` body field, the version must be known
+    Body,
+}
+
+// a Square endpoint, `{id}` in the path is replaced with the object id
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Endpoint {
+    pub(super) method: Method,
+    pub(super) path: &'static str,
+}
+
+const fn get(path: &'static str) -> Endpoint {
+    Endpoint {
+        method: Method::Get,
+        path,
+    }
+}
+
+const fn post(path: &'static str) -> Endpoint {
+    Endpoint {
+        method: Method::Post,
+        path,
+    }
+}
+
+const fn put(path: &'static str) -> Endpoint {
+    Endpoint {
+        method: Method::Put,
+        path,
+    }
+}
+
+const fn delete(path: &'static str) -> Endpoint {
+    Endpoint {
+        method: Method::Delete,
+        path,
+    }
+}
+
+// rows nested in a parent object, scanned as one row per array element
+#[derive(Debug)]
+pub(super) struct Nested {
+    // the object whose scan yields the parent rows
+    pub(super) parent: &'static str,
+    // JSON pointer to the array in each parent row
+    pub(super) pointer: &'static str,
+    // field added to each element to hold the parent id
+    pub(super) parent_id_field: &'static str,
+}
+
+// where a batch retrieve response keeps the objects
+#[derive(Debug)]
+pub(super) enum BatchRows {
+    // an array under the key
+    Array(&'static str),
+    // a map from id to a response holding the object under the second key
+    Map(&'static str, &'static str),
+}
+
+// endpoint retrieving several objects by id in one request
+#[derive(Debug)]
+pub(super) struct Batch {
+    pub(super) endpoint: Endpoint,
+    // request body field holding the ids
+    pub(super) ids_field: &'static str,
+    pub(super) rows: BatchRows,
+}
+
+// a Square object that can be used as the `object` table option
+#[derive(Debug)]
+pub(super) struct SquareObject {
+    pub(super) name: &'static str,
+    // list or search endpoint used by scans
+    pub(super) list: Endpoint,
+    // request body of the search endpoint
+    pub(super) list_body: &'static str,
+    pub(super) search: Option<SearchFn>,
+    // response field holding the rows
+    pub(super) array_key: &'static str,
+    pub(super) paging: Paging,
+    pub(super) since: Option<Since>,
+    // endpoint retrieving one object by id
+    pub(super) get: Option<Endpoint>,
+    // response field holding the object retrieved by id
+    pub(super) object_key: &'static str,
+    pub(super) batch: Option<Batch>,
+    // filters the rows retrieved by id, objects that are location scoped are
+    // also filtered by their `location_id`
+    pub(super) scope: Option<ScopeFn>,
+    // scans need the `location_id` table option
+    pub(super) location_scoped: bool,
+    // fills in fields of each scanned row
+    pub(super) normalize: Option<fn(&mut JsonValue)>,
+    // field wrapping the object in create and update bodies, e.g. `order`
+    pub(super) body_key: Option<&'static str>,
+    // row fields sent next to `body_key` instead of inside it, e.g. the
+    // `location_id` of a new gift card
+    pub(super) top_level_fields: &'static [&'static str],
+    // updates also send the id in the object body
+    pub(super) id_in_body: bool,
+    pub(super) insert: Option<Endpoint>,
+    pub(super) update: Option<Endpoint>,
+    pub(super) delete: Option<Endpoint>,
+    // checks and reshapes the row of insert and update actions
+    pub(super) prepare_insert: Option<PrepareFn>,
+    pub(super) prepare_update: Option<PrepareFn>,
+    pub(super) delete_version: DeleteVersion,
+    // derived objects flattened from a parent object's rows
+    pub(super) nested: Option<Nested>,
+}
+
+// defaults for a read-only object listed by a GET endpoint with cursor query
+const READ_ONLY: SquareObject = SquareObject {
+    name: "",
+    list: get(""),
+    list_body: "{}",
+    search: None,
+    array_key: "",
+    paging: QUERY_CURSOR,
+    since: None,
+    get: None,
+    object_key: "",
+    batch: None,
+    scope: None,
+    location_scoped: false,
+    normalize: None,
+    body_key: None,
+    top_level_fields: &[],
+    id_in_body: false,
+    insert: None,
+    update: None,
+    delete: None,
+    prepare_insert: None,
+    prepare_update: None,
+    delete_version: DeleteVersion::None,
+    nested: None,
+};
+
+// all supported objects, see https://developer.squareup.com/reference/square
+pub(super) const OBJECTS: &[SquareObject] = &[
+    SquareObject {
+        name: "customers",
+        list: get("customers"),
+        array_key: "customers",
+        get: Some(get("customers/{id}")),
+        object_key: "customer",
+        batch: Some(Batch {
+            endpoint: post("customers/bulk-retrieve"),
+            ids_field: "customer_ids",
+            rows: BatchRows::Map("responses", "customer"),
+        }),
+        insert: Some(post("customers")),
+        update: Some(put("customers/{id}")),
+        delete: Some(delete("customers/{id}")),
+        delete_version: DeleteVersion::Query,
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "invoices",
+        list: get("invoices"),
+        array_key: "invoices",
+        get: Some(get("invoices/{id}")),
+        object_key: "invoice",
+        location_scoped: true,
+        body_key: Some("invoice"),
+        id_in_body: true,
+        insert: Some(post("invoices")),
+        update: Some(put("invoices/{id}")),
+        delete: Some(post("invoices/{id}/cancel")),
+        delete_version: DeleteVersion::Body,
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "payments",
+        list: get("payments"),
+        array_key: "payments",
+        since: Some(Since::Query("updated_at_begin_time")),
+        get: Some(get("payments/{id}")),
+        object_key: "payment",
+        insert: Some(post("payments")),
+        update: Some(post("payments/{id}/complete")),
+        delete: Some(post("payments/{id}/cancel")),
+        prepare_update: Some(complete_payment),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "orders",
+        list: post("orders/search"),
+        list_body: "{\"limit\": 100}",
+        array_key: "orders",
+        paging: BODY_CURSOR,
+        get: Some(get("orders/{id}")),
+        object_key: "order",
+        batch: Some(Batch {
+            endpoint: post("orders/batch-retrieve"),
+            ids_field: "order_ids",
+            rows: BatchRows::Array("orders"),
+        }),
+        location_scoped: true,
+        body_key: Some("order"),
+        insert: Some(post("orders")),
+        update: Some(put("orders/{id}")),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "order_line_items",
+        nested: Some(Nested {
+            parent: "orders",
+            pointer: "/line_items",
+            parent_id_field: "order_id",
+        }),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "order_fulfillments",
+        nested: Some(Nested {
+            parent: "orders",
+            pointer: "/fulfillments",
+            parent_id_field: "order_id",
+        }),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "order_tenders",
+        nested: Some(Nested {
+            parent: "orders",
+            pointer: "/tenders",
+            parent_id_field: "order_id",
+        }),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "catalog",
+        list: post("catalog/search"),
+        search: Some(catalog_search),
+        array_key: "objects",
+        paging: BODY_CURSOR,
+        since: Some(Since::Body("begin_time")),
+        get: Some(get("catalog/object/{id}")),
+        object_key: "object",
+        batch: Some(Batch {
+            endpoint: post("catalog/batch-retrieve"),
+            ids_field: "object_ids",
+            rows: BatchRows::Array("objects"),
+        }),
+        scope: Some(catalog_scope),
+        normalize: Some(catalog_name),
+        body_key: Some("object"),
+        id_in_body: true,
+        insert: Some(post("catalog/object")),
+        update: Some(post("catalog/object")),
+        delete: Some(delete("catalog/object/{id}")),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "catalog_item_variations",
+        nested: Some(Nested {
+            parent: "catalog",
+            pointer: "/item_data/variations",
+            parent_id_field: "item_id",
+        }),
+        normalize: Some(catalog_name),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "locations",
+        list: get("locations"),
+        array_key: "locations",
+        paging: Paging::None,
+        get: Some(get("locations/{id}")),
+        object_key: "location",
+        body_key: Some("location"),
+        insert: Some(post("locations")),
+        update: Some(put("locations/{id}")),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "team_members",
+        list: post("team-members/search"),
+        array_key: "team_members",
+        paging: BODY_CURSOR,
+        get: Some(get("team-members/{id}")),
+        object_key: "team_member",
+        body_key: Some("team_member"),
+        insert: Some(post("team-members")),
+        update: Some(put("team-members/{id}")),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "refunds",
+        list: get("refunds"),
+        array_key: "refunds",
+        get: Some(get("refunds/{id}")),
+        object_key: "refund",
+        insert: Some(post("refunds/refund-payment")),
+        prepare_insert: Some(refund_payment),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "disputes",
+        list: get("disputes"),
+        array_key: "disputes",
+        get: Some(get("disputes/{id}")),
+        object_key: "dispute",
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "inventory_counts",
+        list: post("inventory/counts/batch-retrieve"),
+        search: Some(inventory_counts_search),
+        array_key: "counts",
+        paging: BODY_CURSOR,
+        since: Some(Since::Body("updated_after")),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "inventory_changes",
+        list: post("inventory/changes/batch-retrieve"),
+        array_key: "changes",
+        paging: BODY_CURSOR,
+        insert: Some(post("inventory/changes/batch-create")),
+        prepare_insert: Some(inventory_change),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "gift_cards",
+        list: get("gift-cards"),
+        array_key: "gift_cards",
+        get: Some(get("gift-cards/{id}")),
+        object_key: "gift_card",
+        body_key: Some("gift_card"),
+        top_level_fields: &["location_id"],
+        insert: Some(post("gift-cards")),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "loyalty_accounts",
+        list: post("loyalty/accounts/search"),
+        array_key: "loyalty_accounts",
+        paging: BODY_CURSOR,
+        get: Some(get("loyalty/accounts/{id}")),
+        object_key: "loyalty_account",
+        body_key: Some("loyalty_account"),
+        insert: Some(post("loyalty/accounts")),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "subscriptions",
+        list: post("subscriptions/search"),
+        array_key: "subscriptions",
+        paging: BODY_CURSOR,
+        get: Some(get("subscriptions/{id}")),
+        object_key: "subscription",
+        insert: Some(post("subscriptions")),
+        delete: Some(post("subscriptions/{id}/cancel")),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "customer_groups",
+        list: get("customers/groups"),
+        array_key: "groups",
+        get: Some(get("customers/groups/{id}")),
+        object_key: "group",
+        body_key: Some("group"),
+        insert: Some(post("customers/groups")),
+        update: Some(put("customers/groups/{id}")),
+        delete: Some(delete("customers/groups/{id}")),
+        ..READ_ONLY
+    },
+    SquareObject {
+        name: "customer_segments",
+        list: get("customers/segments"),
+        array_key: "segments",
+        get: Some(get("customers/segments/{id}")),
+        object_key: "segment",
+        ..READ_ONLY
+    },
+];
+
+pub(super) fn find_object(name: &str) -> Result<&'static SquareObject, FdwError> {
+    OBJECTS
+        .iter()
+        .find(|obj| obj.name == name)
+        .ok_or(format!("Unknown object type: {}", name))
+}
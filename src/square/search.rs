@@ -0,0 +1,208 @@
+use serde_json::{json, Value as JsonValue};
+
+use crate::bindings::supabase::wrappers::{
+    time,
+    types::{Cell, Context, FdwError, OptionsType, Value},
+};
+
+// a qual that can be pushed down, as (field, operator, value). The value is
+// an array only for `IN (...)` lists.
+pub(super) type PushdownQual = (String, String, Value);
+
+// fills in a search request body from table options and pushed down quals,
+// returns the columns of the quals it sent
+pub(super) type SearchFn =
+    fn(&Context, &[PushdownQual], &mut JsonValue) -> Result<Vec<String>, FdwError>;
+
+// whether a row retrieved by id is one the table's search would return, as
+// lookups by id skip the search body the table options fill in
+pub(super) type ScopeFn = fn(&Context, &JsonValue) -> bool;
+
+// quals comparing a column with one value or, for `IN (...)`, any of a list
+pub(super) fn pushdown_quals(ctx: &Context) -> Vec<PushdownQual> {
+    ctx.get_quals()
+        .iter()
+        .filter(|qual| match qual.value() {
+            Value::Cell(_) => !qual.use_or(),
+            Value::Array(_) => qual.use_or() && qual.operator() == "=",
+        })
+        .map(|qual| (qual.field(), qual.operator(), qual.value()))
+        .collect()
+}
+
+// the strings of a `= 'x'` or `IN ('x', 'y')` qual value
+fn qual_strings(value: &Value) -> Option<Vec<String>> {
+    let as_string = |cell: &Cell| match cell {
+        Cell::String(s) => Some(s.clone()),
+        _ => None,
+    };
+    match value {
+        Value::Cell(cell) => as_string(cell).map(|s| vec![s]),
+        Value::Array(cells) => cells.iter().map(as_string).collect(),
+    }
+}
+
+// the ids asked for by an `id = ...` or `id IN (...)` qual
+pub(super) fn id_quals(quals: &[PushdownQual]) -> Option<Vec<String>> {
+    quals
+        .iter()
+        .filter(|(field, operator, _)| field == "id" && operator == "=")
+        .find_map(|(_, _, value)| qual_strings(value))
+}
+
+// the prefix of a LIKE pattern such as `abc%`, if it has no other wildcards
+fn like_prefix(pattern: &str) -> Option<&str> {
+    pattern
+        .strip_suffix('%')
+        .filter(|prefix| !prefix.is_empty() && !prefix.contains(['%', '_', '\\']))
+}
+
+// search body of `catalog`, the `types` and `include_deleted_objects` table
+// options select the objects, and quals on `name` and `updated_at` are
+// pushed down, see
+// https://developer.squareup.com/reference/square/catalog-api/search-catalog-objects
+pub(super) fn catalog_search(
+    ctx: &Context,
+    quals: &[PushdownQual],
+    body: &mut JsonValue,
+) -> Result<Vec<String>, FdwError> {
+    let opts = ctx.get_options(OptionsType::Table);
+    body["object_types"] = json!(catalog_types(ctx));
+    body["include_deleted_objects"] =
+        json!(opts.require_or("include_deleted_objects", "false") == "true");
+
+    let mut pushed = Vec::new();
+    for (field, operator, value) in quals {
+        match (field.as_str(), operator.as_str(), value) {
+            ("name", "=", Value::Cell(Cell::String(name))) => {
+                body["query"] = json!({
+                    "exact_query": { "attribute_name": "name", "attribute_value": name }
+                });
+                pushed.push(field.clone());
+            }
+            ("name", "~~", Value::Cell(Cell::String(pattern))) if body.get("query").is_none() => {
+                if let Some(prefix) = like_prefix(pattern) {
+                    body["query"] = json!({
+                        "prefix_query": { "attribute_name": "name", "attribute_prefix": prefix }
+                    });
+                    pushed.push(field.clone());
+                }
+            }
+            (
+                "updated_at",
+                ">" | ">=",
+                Value::Cell(Cell::Timestamp(ts) | Cell::Timestamptz(ts)),
+            ) => {
+                body["begin_time"] = json!(time::epoch_ms_to_rfc3339(*ts)?);
+                pushed.push(field.clone());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(pushed)
+}
+
+// the catalog object types of the `types` table option
+fn catalog_types(ctx: &Context) -> Vec<String> {
+    ctx.get_options(OptionsType::Table)
+        .require_or("types", "ITEM")
+        .split(',')
+        .map(|t| t.trim().to_uppercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+// catalog objects retrieved by id are of the `types` table option, and not
+// deleted unless `include_deleted_objects` is set
+pub(super) fn catalog_scope(ctx: &Context, row: &JsonValue) -> bool {
+    let opts = ctx.get_options(OptionsType::Table);
+    let deleted = row["is_deleted"].as_bool().unwrap_or(false);
+    if deleted && opts.require_or("include_deleted_objects", "false") != "true" {
+        return false;
+    }
+    row["type"]
+        .as_str()
+        .is_some_and(|t| catalog_types(ctx).iter().any(|ty| ty == t))
+}
+
+// give a catalog object a top-level `name` from its type's data, e.g.
+// `item_data.name` for an `ITEM`, so the `name` column and the `name` quals
+// sent to Square work without a `columns` mapping
+pub(super) fn catalog_name(row: &mut JsonValue) {
+    if row.get("name").is_some() {
+        return;
+    }
+    let Some(data_key) = row["type"]
+        .as_str()
+        .map(|t| format!("{}_data", t.to_lowercase()))
+    else {
+        return;
+    };
+    if let Some(name) = row[&data_key].get("name").cloned() {
+        row["name"] = name;
+    }
+}
+
+// search body of `inventory_counts`, quals on `catalog_object_id` and
+// `location_id` select the counts and `calculated_at > ...` is sent as
+// `updated_after`, see
+// https://developer.squareup.com/reference/square/inventory-api/batch-retrieve-inventory-counts
+pub(super) fn inventory_counts_search(
+    _ctx: &Context,
+    quals: &[PushdownQual],
+    body: &mut JsonValue,
+) -> Result<Vec<String>, FdwError> {
+    let mut pushed = Vec::new();
+    for (field, operator, value) in quals {
+        match (field.as_str(), operator.as_str(), value) {
+            ("catalog_object_id", "=", value) => {
+                if let Some(ids) = qual_strings(value) {
+                    body["catalog_object_ids"] = json!(ids);
+                    pushed.push(field.clone());
+                }
+            }
+            ("location_id", "=", value) => {
+                if let Some(ids) = qual_strings(value) {
+                    body["location_ids"] = json!(ids);
+                    pushed.push(field.clone());
+                }
+            }
+            (
+                "calculated_at",
+                ">" | ">=",
+                Value::Cell(Cell::Timestamp(ts) | Cell::Timestamptz(ts)),
+            ) => {
+                body["updated_after"] = json!(time::epoch_ms_to_rfc3339(*ts)?);
+                pushed.push(field.clone());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(pushed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_rows_get_a_name_from_their_type_data() {
+        let mut row = json!({"type": "ITEM", "item_data": {"name": "Latte"}});
+        catalog_name(&mut row);
+        assert_eq!(row["name"], "Latte");
+
+        let mut row = json!({"type": "ITEM_VARIATION", "item_variation_data": {"name": "Large"}});
+        catalog_name(&mut row);
+        assert_eq!(row["name"], "Large");
+
+        let mut row = json!({"type": "TAX", "name": "kept", "tax_data": {"name": "VAT"}});
+        catalog_name(&mut row);
+        assert_eq!(row["name"], "kept");
+
+        let mut row = json!({"type": "IMAGE", "image_data": {}});
+        catalog_name(&mut row);
+        assert!(row.get("name").is_none());
+    }
+}
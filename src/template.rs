@@ -0,0 +1,273 @@
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+use crate::bindings::supabase::wrappers::types::{Cell, Context, FdwError, Options, Value};
+use crate::url_encode;
+
+// how placeholder values are written into a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Escape {
+    // URL templates, qual values are percent-encoded while option values
+    // are taken as they are, so they can hold paths
+    Url,
+    // JSON body templates, values are escaped as JSON string content
+    Json,
+}
+
+// values of `{name}` placeholders, looked up in equality quals on the
+// scanned columns, then in `values` and `vars`, then in table and server
+// options
+pub(crate) struct Placeholders<'a> {
+    quals: HashMap<String, String>,
+    // quals whose value went into a template
+    used_quals: Vec<(String, String)>,
+    columns: Vec<String>,
+    // data values, escaped like qual values
+    values: Vec<(&'static str, String)>,
+    // values taken as they are, like options
+    vars: Vec<(&'static str, String)>,
+    opts: Vec<&'a Options>,
+}
+
+impl<'a> Placeholders<'a> {
+    pub(crate) fn new(ctx: &Context, opts: Vec<&'a Options>) -> Self {
+        let quals = ctx
+            .get_quals()
+            .iter()
+            .filter(|qual| qual.operator() == "=" && !qual.use_or())
+            .filter_map(|qual| match qual.value() {
+                Value::Cell(cell) => cell_text(&cell).map(|v| (qual.field(), v)),
+                Value::Array(_) => None,
+            })
+            .collect();
+        Self {
+            quals,
+            used_quals: Vec::new(),
+            columns: ctx.get_columns().iter().map(|col| col.name()).collect(),
+            values: Vec::new(),
+            vars: Vec::new(),
+            opts,
+        }
+    }
+
+    pub(crate) fn with(mut self, name: &'static str, value: String) -> Self {
+        self.vars.push((name, value));
+        self
+    }
+
+    // add a data value such as the watermark, which is percent-encoded in
+    // URL templates
+    pub(crate) fn with_value(mut self, name: &'static str, value: String) -> Self {
+        self.values.push((name, value));
+        self
+    }
+
+    // the quals the request was made for, the API scopes its rows by them
+    // and may not return the column
+    pub(crate) fn used_quals(&self) -> &[(String, String)] {
+        &self.used_quals
+    }
+
+    fn value(&mut self, name: &str, escape: Escape) -> Result<String, FdwError> {
+        let escaped = |value: &str| match escape {
+            Escape::Url => url_encode(value),
+            Escape::Json => value.to_owned(),
+        };
+        if let Some(value) = self.quals.get(name) {
+            self.used_quals.push((name.to_owned(), value.clone()));
+            return Ok(escaped(value));
+        }
+        if let Some((_, value)) = self.values.iter().find(|(var, _)| *var == name) {
+            return Ok(escaped(value));
+        }
+        let value = self
+            .vars
+            .iter()
+            .find(|(var, _)| *var == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| self.opts.iter().find_map(|opts| opts.get(name)));
+        match value {
+            Some(value) => Ok(value),
+            // a column placeholder needs the query to say which value it wants
+            None if self.columns.iter().any(|col| col == name) => Err(format!(
+                "qual required: add `WHERE {} = ...` to the query, the request needs a value for {{{}}}",
+                name, name
+            )),
+            None => Err(format!("no value for placeholder {{{}}}", name)),
+        }
+    }
+}
+
+// replace `{name}` placeholders in a template
+pub(crate) fn fill_template(
+    template: &str,
+    placeholders: &mut Placeholders,
+    escape: Escape,
+) -> Result<String, FdwError> {
+    let mut result = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_len = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+        let name = &after[..name_len];
+
+        // only `{identifier}` is a placeholder, other braces are literal
+        if name.is_empty() || !after[name_len..].starts_with('}') {
+            result.push('{');
+            rest = after;
+            continue;
+        }
+
+        let value = placeholders.value(name, escape)?;
+        if escape == Escape::Json {
+            let quoted = JsonValue::String(value).to_string();
+            result.push_str(&quoted[1..quoted.len() - 1]);
+        } else {
+            result.push_str(&value);
+        }
+        rest = &after[name_len + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+// the text of a qual value that can go into a request
+pub(crate) fn cell_text(cell: &Cell) -> Option<String> {
+    match cell {
+        Cell::Bool(v) => Some(v.to_string()),
+        Cell::I8(v) => Some(v.to_string()),
+        Cell::I16(v) => Some(v.to_string()),
+        Cell::I32(v) => Some(v.to_string()),
+        Cell::I64(v) => Some(v.to_string()),
+        Cell::F32(v) => Some(v.to_string()),
+        Cell::F64(v) => Some(v.to_string()),
+        Cell::Numeric(v) => Some(v.to_string()),
+        Cell::String(v) => Some(v.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // placeholders without options, which need a context
+    fn placeholders(quals: &[(&str, &str)], columns: &[&str]) -> Placeholders<'static> {
+        Placeholders {
+            quals: quals
+                .iter()
+                .map(|(col, value)| (col.to_string(), value.to_string()))
+                .collect(),
+            used_quals: Vec::new(),
+            columns: columns.iter().map(|col| col.to_string()).collect(),
+            values: Vec::new(),
+            vars: Vec::new(),
+            opts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn url_templates_encode_qual_values() {
+        let mut ph = placeholders(&[("owner", "a b/c"), ("repo", "x&y=z")], &[]);
+        let path = fill_template("repos/{owner}/{repo}/issues", &mut ph, Escape::Url).unwrap();
+        assert_eq!(path, "repos/a%20b%2Fc/x%26y%3Dz/issues");
+        assert_eq!(
+            ph.used_quals(),
+            [
+                ("owner".to_owned(), "a b/c".to_owned()),
+                ("repo".to_owned(), "x&y=z".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn url_templates_keep_vars_as_they_are() {
+        let mut ph = placeholders(&[], &[]).with("base_url", "https://x.test/v1".to_owned());
+        let url = fill_template("{base_url}/items?since={base_url}", &mut ph, Escape::Url).unwrap();
+        assert_eq!(url, "https://x.test/v1/items?since=https://x.test/v1");
+        assert!(ph.used_quals().is_empty());
+    }
+
+    #[test]
+    fn values_are_escaped_like_quals() {
+        let mut ph =
+            placeholders(&[], &[]).with_value("watermark", "2024-01-01T00:00:00+00:00".to_owned());
+        assert_eq!(
+            fill_template("issues?since={watermark}", &mut ph, Escape::Url).unwrap(),
+            "issues?since=2024-01-01T00%3A00%3A00%2B00%3A00"
+        );
+        assert_eq!(
+            fill_template(r#"{"since": "{watermark}"}"#, &mut ph, Escape::Json).unwrap(),
+            r#"{"since": "2024-01-01T00:00:00+00:00"}"#
+        );
+        assert!(ph.used_quals().is_empty());
+    }
+
+    #[test]
+    fn quals_take_precedence_over_vars() {
+        let mut ph = placeholders(&[("watermark", "q")], &[]).with("watermark", "v".to_owned());
+        assert_eq!(
+            fill_template("{watermark}", &mut ph, Escape::Url).unwrap(),
+            "q"
+        );
+    }
+
+    #[test]
+    fn json_templates_escape_string_content() {
+        let mut ph = placeholders(&[("name", "say \"hi\"\\\n")], &[]);
+        let body = fill_template(r#"{"name": "{name}"}"#, &mut ph, Escape::Json).unwrap();
+        assert_eq!(body, r#"{"name": "say \"hi\"\\\n"}"#);
+        let parsed: JsonValue = serde_json::from_str(&body).unwrap();
+        assert_eq!(parsed["name"], "say \"hi\"\\\n");
+    }
+
+    #[test]
+    fn other_braces_are_literal() {
+        let mut ph = placeholders(&[("id", "7")], &[]);
+        let cases = [
+            (r#"{"query": {"id": "{id}"}}"#, r#"{"query": {"id": "7"}}"#),
+            ("{}", "{}"),
+            ("{ id }", "{ id }"),
+            ("{id", "{id"),
+            ("{{id}}", "{7}"),
+            ("{a-b}", "{a-b}"),
+            ("}{id}{", "}7{"),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(
+                fill_template(template, &mut ph, Escape::Json).unwrap(),
+                expected,
+                "{}",
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn column_placeholders_need_a_qual() {
+        let mut ph = placeholders(&[], &["owner"]);
+        let err = fill_template("repos/{owner}", &mut ph, Escape::Url).unwrap_err();
+        assert!(err.contains("WHERE owner = ..."), "{}", err);
+    }
+
+    #[test]
+    fn unknown_placeholders_fail() {
+        let mut ph = placeholders(&[], &[]);
+        let err = fill_template("items/{missing}", &mut ph, Escape::Url).unwrap_err();
+        assert_eq!(err, "no value for placeholder {missing}");
+    }
+
+    #[test]
+    fn cell_text_of_qual_values() {
+        assert_eq!(cell_text(&Cell::I64(42)).as_deref(), Some("42"));
+        assert_eq!(cell_text(&Cell::Bool(true)).as_deref(), Some("true"));
+        assert_eq!(
+            cell_text(&Cell::String("a".to_owned())).as_deref(),
+            Some("a")
+        );
+        assert_eq!(cell_text(&Cell::Json("{}".to_owned())), None);
+    }
+}